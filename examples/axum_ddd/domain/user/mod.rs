@@ -1,5 +1,9 @@
+use anyhow::Context;
 use entity::Permission;
-use moonbase::{extract::TryExtractFrom, Moonbase};
+use moonbase::{
+    extension::axum::RequestContext, extract::TryExtractFrom, module::Dependency,
+    resource::MoonbaseResource, Moonbase,
+};
 
 pub mod entity;
 pub mod notification;
@@ -9,26 +13,54 @@ pub struct Service<R, N> {
     repository: R,
     notification: N,
     permission: Permission,
-    app_context: Moonbase,
 }
 
 impl<R, N> Service<R, N> {
-    pub fn new(app_context: Moonbase, repository: R, notification: N) -> Self {
+    pub fn new(repository: R, notification: N) -> Self {
         Self {
             repository,
             notification,
-            app_context,
             permission: Permission::User,
         }
     }
 }
 
+/// A service for each request, acting with the permission of the authenticated principal.
+impl<R> TryExtractFrom<RequestContext> for Service<R, Moonbase>
+where
+    R: repository::UserRepository + MoonbaseResource,
+{
+    type Error = anyhow::Error;
+
+    async fn try_extract_from(context: &RequestContext) -> Result<Self, Self::Error> {
+        let repository = context
+            .moonbase()
+            .get_resource::<R>()
+            .with_context(|| "user repository not found")?;
+        let permission = match context.principal() {
+            Some(principal) if principal.has_role("admin") => Permission::Admin,
+            _ => Permission::User,
+        };
+        Ok(Self {
+            repository,
+            notification: context.moonbase().clone(),
+            permission,
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<R>()]
+    }
+}
+
 impl<R, N> Service<R, N>
 where
     R: repository::UserRepository,
     N: notification::UserNotification,
 {
     pub async fn create_user(&self, user: entity::User) -> Result<(), anyhow::Error> {
+        if user.permission == Permission::Admin && self.permission != Permission::Admin {
+            anyhow::bail!("only admins can create admins");
+        }
         let user_id = user.id;
         self.repository.create(user).await?;
         self.notification.notify_user_created(user_id).await?;
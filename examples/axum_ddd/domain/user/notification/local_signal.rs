@@ -1,10 +1,5 @@
-use std::sync::Arc;
-
 use anyhow::Context;
-use moonbase::{
-    signal::{Signal, SignalSymbol},
-    Moonbase,
-};
+use moonbase::Moonbase;
 use tokio::sync::mpsc;
 
 use super::UserNotification;
@@ -1,5 +1,57 @@
+use axum::{http::StatusCode, routing::post, Json};
+use domain::user::{entity::User, notification::local_signal::UserEvent, Service};
+use moonbase::{
+    extension::axum::{request_context, Authenticator, Principal, Scoped},
+    Moonbase,
+};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use tokio::sync::mpsc;
+
 pub mod domain;
-#[tokio::main]
-async fn main() {
 
-}
\ No newline at end of file
+type UserService = Service<Surreal<Client>, Moonbase>;
+
+async fn create_user(
+    Scoped(service): Scoped<UserService>,
+    Json(user): Json<User>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    service
+        .create_user(user)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(StatusCode::CREATED)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(Surreal::<Client>::init());
+    let (events, mut received) = mpsc::channel::<UserEvent>(16);
+    moonbase.set_resource(events);
+    // a bearer token is taken as the user id, `root` is an admin
+    moonbase.set_resource(Authenticator::new(|_, headers| async move {
+        let token = headers.get("authorization")?.to_str().ok()?;
+        let id = token.strip_prefix("Bearer ")?;
+        let principal = Principal::new(id);
+        Some(if id == "root" {
+            principal.with_role("admin")
+        } else {
+            principal
+        })
+    }));
+    tokio::spawn(async move {
+        while let Some(UserEvent::Created(user_id)) = received.recv().await {
+            println!("user {user_id} created");
+        }
+    });
+    let router = axum::Router::new()
+        .route("/users", post(create_user))
+        .layer(axum::middleware::from_fn_with_state(
+            moonbase.clone(),
+            request_context,
+        ))
+        .with_state(moonbase);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+    axum::serve(listener, router).await?;
+    Ok(())
+}
@@ -1,9 +1,17 @@
-use std::{convert::Infallible, future::IntoFuture, pin::Pin, time::Duration};
+use std::{convert::Infallible, future::IntoFuture, pin::Pin};
 
-use axum::extract::State;
 use futures::Future;
 use moonbase::{
-    components::ComponentName, context::ContextExt, daemon::Daemon, extension::tsuki_scheduler::{TsukiScheduler, TsukiSchedulerClient}, extract::{ExtractFrom, TryExtractFrom}, module::Module, resource::{MoonbaseResource, Resource}, runtime::Tokio, signal::{Signal, SignalKey}, AppContext, Moonbase
+    components::ComponentName,
+    context::ContextExt,
+    daemon::Daemon,
+    extension::tsuki_scheduler::{TsukiScheduler, TsukiSchedulerClient},
+    extract::{Extract, TryExtractFrom},
+    module::{Dependency, Module},
+    resource::Resource,
+    runtime::Tokio,
+    signal::{Signal, SignalKey},
+    AppContext, Moonbase,
 };
 use tsuki_scheduler::{Task, TaskUid};
 
@@ -17,18 +25,22 @@ fn main() {
 }
 
 async fn async_main() -> anyhow::Result<()> {
-    let moonbase = Moonbase::new();
+    let moonbase = Moonbase::builder()
+        .module(HelloModule {})
+        .module(Tokio::default())
+        .build()
+        .await?;
     moonbase.call(init_resource).await?;
     moonbase.call(async_with_result).await?;
     moonbase.call(no_result).await;
-    moonbase.load_module(Tokio::default()).await?;
-    moonbase.load_module(HelloModule {}).await?;
     moonbase.run_daemon::<AxumServerDaemon>().await?;
     moonbase.run_daemon::<TsukiScheduler>().await?;
     let client = moonbase.get_resource::<TsukiSchedulerClient>().unwrap();
     let handle = moonbase.run_daemon::<MyDaemon>().await?;
     moonbase.set_signal(SignalKey::symbol::<Moonbase>(), Signal::new());
-    let signal = moonbase.get_signal(&SignalKey::symbol::<Moonbase>()).unwrap();
+    let signal = moonbase
+        .get_signal(&SignalKey::symbol::<Moonbase>())
+        .unwrap();
     signal.get_sender().send();
     signal.recv().await;
     client.add_task(
@@ -39,7 +51,10 @@ async fn async_main() -> anyhow::Result<()> {
     );
     handle.wait().await;
 
-    tokio::signal::ctrl_c().await.unwrap();
+    let report = moonbase
+        .run_until_terminated(std::time::Duration::from_secs(5))
+        .await?;
+    println!("{report}");
     Ok(())
 }
 
@@ -47,76 +62,38 @@ async fn init_resource() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn init_resource_infallible() {}
-
 async fn async_with_result(
-    res: MyResource,
-    res2: Result<MyFallibleResource, anyhow::Error>,
+    _res: MyResource,
+    _res2: Result<MyFallibleResource, anyhow::Error>,
 ) -> Result<(), Infallible> {
     Ok(())
 }
 async fn no_result() {}
-fn sync() {}
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Extract)]
+#[extract(infallible)]
 pub struct MyResource {}
-impl MoonbaseResource for MyResource {}
 
-impl ExtractFrom<Moonbase> for MyResource {
-    async fn extract_from(_moonbase: &Moonbase) -> Self {
-        MyResource {}
-    }
-}
-impl TryExtractFrom<Moonbase> for MyResource {
-    type Error = Infallible;
-    async fn try_extract_from(_moonbase: &Moonbase) -> Result<Self, Infallible> {
-        Ok(MyResource {})
-    }
-}
 pub struct MyFallibleResource {}
 impl TryExtractFrom<Moonbase> for MyFallibleResource {
     type Error = anyhow::Error;
     async fn try_extract_from(_moonbase: &Moonbase) -> anyhow::Result<Self> {
         Ok(MyFallibleResource {})
     }
-}
-
-#[derive(Debug)]
-pub struct MyDaemon {}
-
-impl ExtractFrom<Moonbase> for MyDaemon {
-    async fn extract_from(_moonbase: &Moonbase) -> Self {
-        MyDaemon {}
-    }
-}
-
-impl TryExtractFrom<Moonbase> for MyDaemon {
-    type Error = Infallible;
-
-    async fn try_extract_from(_moonbase: &Moonbase) -> Result<Self, Self::Error> {
-        Ok(MyDaemon {})
-    }
-}
-
-impl IntoFuture for MyDaemon {
-    type Output = Self;
-    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
-
-    fn into_future(self) -> Pin<Box<dyn Future<Output = Self> + Send>> {
-        Box::pin(async {
-            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-            println!("Hello, Moonbase!");
-            self
-        })
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
     }
 }
 
-impl Daemon<Moonbase> for MyDaemon {
-    fn max_restart_time(&self) -> Option<usize> {
-        Some(2)
-    }
+#[derive(Debug, Extract)]
+#[extract(infallible)]
+pub struct MyDaemon {}
 
-    fn cool_down_time(&self) -> Option<std::time::Duration> {
-        Some(std::time::Duration::from_secs(1))
+#[moonbase::daemon(max_restarts = 2, cool_down = "1s")]
+impl MyDaemon {
+    async fn run(self) -> Self {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        println!("Hello, Moonbase!");
+        self
     }
 }
 pub struct SomeTransitionContext {
@@ -137,41 +114,22 @@ async fn get_handler(mb: Moonbase, Resource(_my_resource): Resource<MyResource>)
 }
 
 impl Module<Moonbase> for HelloModule {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::resource::<Tokio>()]
+    }
     async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
-        let router =
-            axum::Router::new().route("/", axum::routing::get(get_handler));
+        let router = axum::Router::new().route("/", axum::routing::get(get_handler));
         context.insert_axum_router(&ComponentName::new_symbol::<Self>(), router);
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Extract)]
+#[extract(infallible)]
 pub struct AxumServerDaemon {
     context: Moonbase,
 }
 
-impl ExtractFrom<Moonbase> for AxumServerDaemon {
-    async fn extract_from(moonbase: &Moonbase) -> Self {
-        AxumServerDaemon {
-            context: moonbase.clone(),
-        }
-    }
-}
-
-impl TryExtractFrom<Moonbase> for AxumServerDaemon {
-    type Error = Infallible;
-
-    async fn try_extract_from(moonbase: &Moonbase) -> Result<Self, Self::Error> {
-        Ok(AxumServerDaemon {
-            context: moonbase.clone(),
-        })
-    }
-}
-
-pub struct AxumServerState {
-    moonbase: Moonbase,
-}
-
 impl IntoFuture for AxumServerDaemon {
     type Output = Self;
     type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
@@ -181,7 +139,7 @@ impl IntoFuture for AxumServerDaemon {
             let router = self.context.collect_axum_routes();
             let addr = std::net::SocketAddr::from(([127, 0, 0, 1], 3000));
             let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-            axum::serve(listener, router.with_state(self.context.clone())).await;
+            let _ = axum::serve(listener, router.with_state(self.context.clone())).await;
             self
         })
     }
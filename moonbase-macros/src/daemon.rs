@@ -0,0 +1,195 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, Ident, ImplItem, Item, ItemFn, ItemImpl, LitInt, LitStr, Type};
+
+use crate::{extract, function};
+
+#[derive(Default)]
+struct Options {
+    context: Option<Type>,
+    name: Option<Ident>,
+    max_restarts: Option<LitInt>,
+    cool_down: Option<u64>,
+}
+
+impl Options {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("context") {
+            self.context = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("max_restarts") {
+            self.max_restarts = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("cool_down") {
+            let cool_down: LitStr = meta.value()?.parse()?;
+            self.cool_down = Some(parse_millis(&cool_down)?);
+        } else {
+            return Err(meta.error("expected `context`, `name`, `max_restarts` or `cool_down`"));
+        }
+        Ok(())
+    }
+}
+
+/// `"500ms"`, `"1s"`, `"5m"` or `"1h"` in milliseconds.
+fn parse_millis(literal: &LitStr) -> syn::Result<u64> {
+    let value = literal.value();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let scale = match unit.trim() {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => 0,
+    };
+    let number = match number.parse::<u64>() {
+        Ok(number) if scale > 0 => number,
+        _ => {
+            return Err(syn::Error::new(
+                literal.span(),
+                "expected a duration like \"500ms\", \"1s\", \"5m\" or \"1h\"",
+            ))
+        }
+    };
+    number
+        .checked_mul(scale)
+        .ok_or_else(|| syn::Error::new(literal.span(), "the duration is too long"))
+}
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let mut options = Options::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    syn::parse::Parser::parse2(parser, attr)?;
+    match syn::parse2::<Item>(item)? {
+        Item::Fn(function) => expand_fn(options, function),
+        Item::Impl(item) => expand_impl(options, item),
+        item => Err(syn::Error::new(
+            item.span(),
+            "expected an async fn or an impl block",
+        )),
+    }
+}
+
+/// `header` is what follows `impl<..> Trait for`, e.g. `Mailer` or `Mailer<T> where ..`.
+fn daemon_impl(
+    options: &Options,
+    context: &Type,
+    generics: &TokenStream,
+    header: TokenStream,
+) -> TokenStream {
+    let max_restarts = options.max_restarts.as_ref().map(|max_restarts| {
+        quote! {
+            fn max_restart_time(&self) -> ::std::option::Option<usize> {
+                ::std::option::Option::Some(#max_restarts)
+            }
+        }
+    });
+    let cool_down = options.cool_down.map(|millis| {
+        quote! {
+            fn cool_down_time(&self) -> ::std::option::Option<::std::time::Duration> {
+                ::std::option::Option::Some(::std::time::Duration::from_millis(#millis))
+            }
+        }
+    });
+    quote! {
+        impl #generics ::moonbase::daemon::Daemon<#context> for #header {
+            #max_restarts
+            #cool_down
+        }
+    }
+}
+
+fn into_future_impl(generics: &TokenStream, header: TokenStream, body: TokenStream) -> TokenStream {
+    quote! {
+        impl #generics ::std::future::IntoFuture for #header {
+            type Output = ::moonbase::daemon::DaemonOutcome<Self>;
+            type IntoFuture = ::std::pin::Pin<
+                ::std::boxed::Box<dyn ::std::future::Future<Output = Self::Output> + Send>,
+            >;
+            fn into_future(self) -> Self::IntoFuture {
+                ::std::boxed::Box::pin(async move { #body })
+            }
+        }
+    }
+}
+
+/// The daemon is a struct of the extracted parameters, the fn is called for each run.
+fn expand_fn(options: Options, mut function: ItemFn) -> syn::Result<TokenStream> {
+    let params = function::take_params(&mut function)?;
+    let name = options
+        .name
+        .clone()
+        .unwrap_or_else(|| function::pascal_case(&function.sig.ident));
+    let context = options
+        .context
+        .clone()
+        .unwrap_or_else(function::default_context);
+    let (input, definition) = function::params_struct(&function.vis, &name, &context, &params);
+    let extract = extract::expand_with_owner(input, &name.to_string())?;
+    let call = function::call(&function, &name, &params, quote!(self));
+    let into_future = into_future_impl(
+        &TokenStream::new(),
+        quote!(#name),
+        quote!(::moonbase::__private::IntoDaemonOutcome::into_daemon_outcome(#call)),
+    );
+    let daemon = daemon_impl(&options, &context, &TokenStream::new(), quote!(#name));
+    let name_str = name.to_string();
+    Ok(quote! {
+        #function
+        #definition
+        impl ::std::fmt::Debug for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(#name_str).finish_non_exhaustive()
+            }
+        }
+        #extract
+        #into_future
+        #daemon
+    })
+}
+
+/// The impl block has an async `run(self)`, returning anything into [`DaemonOutcome`].
+fn expand_impl(options: Options, item: ItemImpl) -> syn::Result<TokenStream> {
+    if let Some(name) = &options.name {
+        return Err(syn::Error::new(
+            name.span(),
+            "`name` is only for an async fn",
+        ));
+    }
+    let has_run = item.items.iter().any(|item| {
+        matches!(item, ImplItem::Fn(method) if method.sig.ident == "run"
+            && method.sig.asyncness.is_some()
+            && matches!(method.sig.receiver(), Some(receiver) if receiver.reference.is_none()))
+    });
+    if !has_run {
+        return Err(syn::Error::new(
+            item.self_ty.span(),
+            "expected an `async fn run(self)` in the impl block",
+        ));
+    }
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let context = options
+        .context
+        .clone()
+        .unwrap_or_else(function::default_context);
+    let generics = quote!(#impl_generics);
+    let into_future = into_future_impl(
+        &generics,
+        quote!(#self_ty #where_clause),
+        quote!(::std::convert::Into::into(Self::run(self).await)),
+    );
+    let daemon = daemon_impl(
+        &options,
+        &context,
+        &generics,
+        quote!(#self_ty #where_clause),
+    );
+    Ok(quote! {
+        #item
+        #into_future
+        #daemon
+    })
+}
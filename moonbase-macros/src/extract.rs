@@ -0,0 +1,247 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, Data, DeriveInput, Fields, LitStr, Member, Type};
+
+/// How a field is extracted.
+enum Source {
+    Extract,
+    Resource,
+    Component(LitStr),
+}
+
+struct Field {
+    member: Member,
+    ty: Type,
+    source: Source,
+    default: bool,
+}
+
+struct Options {
+    context: Type,
+    infallible: bool,
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let owner = input.ident.to_string();
+    expand_with_owner(input, &owner)
+}
+
+/// `owner` is how the struct is called in errors.
+pub fn expand_with_owner(input: DeriveInput, owner: &str) -> syn::Result<TokenStream> {
+    let options = parse_options(&input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => parse_fields(&data.fields)?,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "`Extract` can only be derived for structs",
+            ))
+        }
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let context = &options.context;
+    let members = fields.iter().map(|field| &field.member);
+    let values = fields
+        .iter()
+        .map(|field| field_value(field, &options, owner))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let requirements = fields
+        .iter()
+        .filter_map(|field| field_requirements(field, &options));
+    let construct = quote!(Self { #(#members: #values,)* });
+    let requirements = quote! {
+        fn requirements() -> ::std::vec::Vec<::moonbase::module::Dependency> {
+            ::std::iter::empty()
+                #(.chain(#requirements))*
+                .collect()
+        }
+    };
+    let expanded = if options.infallible {
+        quote! {
+            impl #impl_generics ::moonbase::extract::ExtractFrom<#context> for #name #ty_generics #where_clause {
+                async fn extract_from(context: &#context) -> Self {
+                    #construct
+                }
+                #requirements
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics ::moonbase::extract::TryExtractFrom<#context> for #name #ty_generics #where_clause {
+                type Error = ::moonbase::__private::anyhow::Error;
+                async fn try_extract_from(context: &#context) -> ::std::result::Result<Self, Self::Error> {
+                    ::std::result::Result::Ok(#construct)
+                }
+                #requirements
+            }
+        }
+    };
+    Ok(expanded)
+}
+
+fn parse_options(input: &DeriveInput) -> syn::Result<Options> {
+    let mut options = Options {
+        context: syn::parse_quote!(::moonbase::Moonbase),
+        infallible: false,
+    };
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("extract"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("context") {
+                options.context = meta.value()?.parse()?;
+            } else if meta.path.is_ident("infallible") {
+                options.infallible = true;
+            } else {
+                return Err(meta.error("expected `context` or `infallible`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+fn parse_fields(fields: &Fields) -> syn::Result<Vec<Field>> {
+    let mut parsed = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+        let mut source = Source::Extract;
+        let mut default = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("extract"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("resource") {
+                    source = Source::Resource;
+                } else if meta.path.is_ident("component") {
+                    source = Source::Component(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    default = true;
+                } else {
+                    return Err(meta.error("expected `resource`, `component` or `default`"));
+                }
+                Ok(())
+            })?;
+        }
+        parsed.push(Field {
+            member,
+            ty: field.ty.clone(),
+            source,
+            default,
+        });
+    }
+    Ok(parsed)
+}
+
+/// How a resource or component field keeps a failed extraction.
+enum Wrapper {
+    /// `None` if it fails
+    Option,
+    /// the error if it fails
+    Result,
+}
+
+/// The `T` of `Option<T>` or `Result<T, E>`.
+fn wrapped_inner(ty: &Type) -> Option<(Wrapper, &Type)> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    let wrapper = if segment.ident == "Option" {
+        Wrapper::Option
+    } else if segment.ident == "Result" {
+        Wrapper::Result
+    } else {
+        return None;
+    };
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) => Some((wrapper, inner)),
+        _ => None,
+    }
+}
+
+fn field_name(member: &Member) -> String {
+    match member {
+        Member::Named(ident) => ident.to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+fn field_value(field: &Field, options: &Options, owner: &str) -> syn::Result<TokenStream> {
+    let context = &options.context;
+    let ty = &field.ty;
+    let name = field_name(&field.member);
+    let (target, wrapper) = match (&field.source, wrapped_inner(ty)) {
+        (Source::Extract, _) | (_, None) => (ty, None),
+        (_, Some((wrapper, inner))) => (inner, Some(wrapper)),
+    };
+    let extraction = match &field.source {
+        Source::Extract => {
+            if options.infallible && !field.default {
+                return Ok(quote! {
+                    <#ty as ::moonbase::extract::ExtractFrom<#context>>::extract_from(context).await
+                });
+            }
+            if let Type::Tuple(_) = ty {
+                quote! {
+                    ::moonbase::__private::try_extract_tuple_field::<#context, #ty>(context, #owner, #name).await
+                }
+            } else {
+                quote! {
+                    ::moonbase::__private::try_extract_field::<#context, #ty>(context, #owner, #name).await
+                }
+            }
+        }
+        Source::Resource => quote! {
+            ::moonbase::__private::extract_resource::<#context, #target>(context, #owner, #name).await
+        },
+        Source::Component(component) => quote! {
+            ::moonbase::__private::extract_component::<#context, #target>(
+                context, #component, #owner, #name,
+            ).await
+        },
+    };
+    match wrapper {
+        Some(Wrapper::Option) => Ok(quote!(#extraction.ok())),
+        Some(Wrapper::Result) => Ok(quote!(#extraction.map_err(::std::convert::Into::into))),
+        None if field.default => Ok(quote!(#extraction.unwrap_or_default())),
+        None if options.infallible => Err(syn::Error::new(
+            ty.span(),
+            "a resource or component field of an infallible extraction must be an `Option`, a `Result` or have `#[extract(default)]`",
+        )),
+        None => Ok(quote!(#extraction?)),
+    }
+}
+
+/// What the field adds to `requirements()`, none if it's optional.
+fn field_requirements(field: &Field, options: &Options) -> Option<TokenStream> {
+    let context = &options.context;
+    let ty = &field.ty;
+    if field.default {
+        return None;
+    }
+    match &field.source {
+        Source::Extract if options.infallible => {
+            Some(quote!(<#ty as ::moonbase::extract::ExtractFrom<#context>>::requirements()))
+        }
+        Source::Extract if matches!(ty, Type::Tuple(_)) => {
+            Some(quote!(<#ty as ::moonbase::extract::TryExtractTuple<#context>>::requirements()))
+        }
+        Source::Extract => {
+            Some(quote!(<#ty as ::moonbase::extract::TryExtractFrom<#context>>::requirements()))
+        }
+        Source::Resource if wrapped_inner(ty).is_none() => {
+            Some(quote!([::moonbase::module::Dependency::resource::<#ty>()]))
+        }
+        Source::Resource | Source::Component(_) => None,
+    }
+}
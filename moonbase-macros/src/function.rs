@@ -0,0 +1,116 @@
+//! Turning an async fn into a struct holding its extracted parameters.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, FnArg, Ident, ItemFn, Pat, Token, Type,
+};
+
+/// A parameter of the fn, extracted into a field named `ident`.
+pub struct Param {
+    pub ident: Ident,
+    pub ty: Type,
+    /// the `#[extract(..)]` attributes
+    pub attrs: Vec<syn::Attribute>,
+}
+
+/// Take the parameters out of an async fn, `#[extract(..)]` attributes are removed from it.
+pub fn take_params(function: &mut ItemFn) -> syn::Result<Vec<Param>> {
+    if function.sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            function.sig.fn_token.span(),
+            "expected an async fn",
+        ));
+    }
+    if !function.sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            function.sig.generics.span(),
+            "generic fn is not supported",
+        ));
+    }
+    let mut params = Vec::new();
+    for (index, input) in function.sig.inputs.iter_mut().enumerate() {
+        let FnArg::Typed(input) = input else {
+            return Err(syn::Error::new(input.span(), "`self` is not extractable"));
+        };
+        let (attrs, others) = std::mem::take(&mut input.attrs)
+            .into_iter()
+            .partition(|attr| attr.path().is_ident("extract"));
+        input.attrs = others;
+        let ident = match &*input.pat {
+            Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+            _ => Ident::new(&format!("arg{index}"), input.pat.span()),
+        };
+        params.push(Param {
+            ident,
+            ty: (*input.ty).clone(),
+            attrs,
+        });
+    }
+    Ok(params)
+}
+
+/// A struct with a field for each parameter, fed to the `Extract` derive.
+pub fn params_struct(
+    vis: &syn::Visibility,
+    name: &Ident,
+    context: &Type,
+    params: &[Param],
+) -> (syn::DeriveInput, TokenStream) {
+    let fields = params
+        .iter()
+        .map(|Param { ident, ty, attrs }| quote!(#(#attrs)* #ident: #ty));
+    let input = parse_quote! {
+        #[extract(context = #context)]
+        struct #name { #(#fields,)* }
+    };
+    let fields = params
+        .iter()
+        .map(|Param { ident, ty, .. }| quote!(#ident: #ty));
+    let definition = quote!(#vis struct #name { #(#fields,)* });
+    (input, definition)
+}
+
+/// Call the fn with the fields of `value`, a struct built by [`params_struct`].
+pub fn call(function: &ItemFn, name: &Ident, params: &[Param], value: TokenStream) -> TokenStream {
+    let fn_name = &function.sig.ident;
+    let idents = params.iter().map(|param| &param.ident).collect::<Vec<_>>();
+    quote! {
+        {
+            let #name { #(#idents,)* } = #value;
+            #fn_name(#(#idents,)*).await
+        }
+    }
+}
+
+/// `mail_daemon` to `MailDaemon`.
+pub fn pascal_case(ident: &Ident) -> Ident {
+    let name = ident
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+    Ident::new(&name, ident.span())
+}
+
+/// `Type, Type, ..` in parentheses.
+pub fn parse_types(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Vec<Type>> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+    Ok(types.into_iter().collect())
+}
+
+pub fn default_context() -> Type {
+    parse_quote!(::moonbase::Moonbase)
+}
+
+pub fn hidden_ident(name: &Ident, suffix: &str) -> Ident {
+    Ident::new(&format!("__{name}{suffix}"), Span::call_site())
+}
@@ -0,0 +1,129 @@
+//! Macros of [moonbase](https://docs.rs/moonbase), use them through the `macros` feature of
+//! moonbase rather than depending on this crate.
+
+use proc_macro::TokenStream;
+
+mod daemon;
+mod extract;
+mod function;
+mod module;
+
+/// Extract a struct field by field from a context.
+///
+/// Fields are extracted by [`TryExtractFrom`], which every [`ExtractFrom`] type implements, or by
+/// `TryExtractTuple` for a tuple, and the generated impl is fallible with an error telling which
+/// field fails.
+///
+/// ```ignore
+/// #[derive(Extract)]
+/// #[extract(context = Moonbase)]
+/// struct UserService {
+///     // extracted by `TryExtractFrom`
+///     context: Moonbase,
+///     // got by `get_or_init_resource`
+///     #[extract(resource)]
+///     repository: UserRepository,
+///     // `None` if it's not set
+///     #[extract(resource)]
+///     mailer: Option<Mailer>,
+///     // the error if it's not set
+///     #[extract(resource)]
+///     config: anyhow::Result<Config>,
+///     // got by `get_component`
+///     #[extract(component = "users-cache")]
+///     cache: Cache,
+///     // `Default::default()` if fails
+///     #[extract(resource, default)]
+///     limit: Limit,
+/// }
+/// ```
+///
+/// Struct attributes:
+/// - `context = Type`: the context to extract from, `moonbase::Moonbase` by default
+/// - `infallible`: implement `ExtractFrom` instead, every resource or component field must be an
+///   `Option`, a `Result` or have a default
+///
+/// Field attributes:
+/// - `resource`: a resource of the field type, or of `T` for an `Option<T>` or a `Result<T, E>`
+///   where `E` is built from an `anyhow::Error`
+/// - `component = "name"`: a component of the field type named `name`, `Option` and `Result`
+///   alike
+/// - `default`: fall back to `Default::default()` if the extraction fails
+///
+/// [`TryExtractFrom`]: https://docs.rs/moonbase/latest/moonbase/extract/trait.TryExtractFrom.html
+/// [`ExtractFrom`]: https://docs.rs/moonbase/latest/moonbase/extract/trait.ExtractFrom.html
+#[proc_macro_derive(Extract, attributes(extract))]
+pub fn derive_extract(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    extract::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turn an async fn or an impl block into a [`Daemon`].
+///
+/// On an async fn, a struct named in pascal case is generated holding the parameters, which are
+/// extracted like the fields of [`Extract`](derive@Extract) and accept the same attributes. The
+/// fn is called for each run, returning `()` stops the daemon and returning an `Err` restarts it
+/// with parameters extracted again. Extraction errors name the struct, e.g.
+/// ``fail to extract field `config` of `Mailer` ``.
+///
+/// ```ignore
+/// #[moonbase::daemon(max_restarts = 3, cool_down = "1s")]
+/// async fn mailer(#[extract(resource)] config: MailConfig) -> anyhow::Result<()> {
+///     /* ... */
+/// }
+///
+/// moonbase.run_daemon::<Mailer>().await?;
+/// ```
+///
+/// On an impl block, the type should implement `TryExtractFrom` and `Debug`, and the block
+/// should have an `async fn run(self)` returning anything into `DaemonOutcome<Self>`.
+///
+/// Options:
+/// - `context = Type`: the context to extract from, `moonbase::Moonbase` by default
+/// - `name = Ident`: the name of the generated struct, only for an async fn
+/// - `max_restarts = 3`: see `Daemon::max_restart_time`
+/// - `cool_down = "1s"`: see `Daemon::cool_down_time`, in `ms`, `s`, `m` or `h`
+///
+/// [`Daemon`]: https://docs.rs/moonbase/latest/moonbase/daemon/trait.Daemon.html
+#[proc_macro_attribute]
+pub fn daemon(attr: TokenStream, item: TokenStream) -> TokenStream {
+    daemon::expand(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turn an async init fn into a [`Module`].
+///
+/// A unit struct named in pascal case is generated, whose initialization calls the fn with the
+/// parameters extracted like the fields of [`Extract`](derive@Extract). What the parameters
+/// require becomes the dependencies of the module. The fn returns `()` or a `Result<(), E>`.
+/// Extraction errors name the struct like for [`daemon`](macro@daemon).
+///
+/// ```ignore
+/// #[moonbase::module(provides(Pool), after(ConfigModule))]
+/// async fn database(
+///     #[extract(resource)] config: Config,
+///     context: Moonbase,
+/// ) -> anyhow::Result<()> {
+///     context.set_resource(Pool::connect(&config.url).await?);
+///     Ok(())
+/// }
+///
+/// Moonbase::builder().module(Database).build().await?;
+/// ```
+///
+/// Options:
+/// - `context = Type`: the context to extract from, `moonbase::Moonbase` by default
+/// - `name = Ident`: the name of the generated struct
+/// - `provides(Type, ..)`: resources set by the module, see `Module::provides`
+/// - `after(Module, ..)`: modules to load before this one
+///
+/// [`Module`]: https://docs.rs/moonbase/latest/moonbase/module/trait.Module.html
+#[proc_macro_attribute]
+pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    module::expand(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
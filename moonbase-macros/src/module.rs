@@ -0,0 +1,77 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, ItemFn, Type};
+
+use crate::{extract, function};
+
+#[derive(Default)]
+struct Options {
+    context: Option<Type>,
+    name: Option<Ident>,
+    provides: Vec<Type>,
+    after: Vec<Type>,
+}
+
+impl Options {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("context") {
+            self.context = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("provides") {
+            self.provides.extend(function::parse_types(&meta)?);
+        } else if meta.path.is_ident("after") {
+            self.after.extend(function::parse_types(&meta)?);
+        } else {
+            return Err(meta.error("expected `context`, `name`, `provides` or `after`"));
+        }
+        Ok(())
+    }
+}
+
+/// The module is a unit struct, initialized by calling the fn with the extracted parameters.
+pub fn expand(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let mut options = Options::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    syn::parse::Parser::parse2(parser, attr)?;
+    let mut function = syn::parse2::<ItemFn>(item)?;
+    let params = function::take_params(&mut function)?;
+    let name = options
+        .name
+        .clone()
+        .unwrap_or_else(|| function::pascal_case(&function.sig.ident));
+    let context = options.context.unwrap_or_else(function::default_context);
+    let args = function::hidden_ident(&name, "Args");
+    let (input, definition) =
+        function::params_struct(&syn::Visibility::Inherited, &args, &context, &params);
+    let extract = extract::expand_with_owner(input, &name.to_string())?;
+    let call = function::call(
+        &function,
+        &args,
+        &params,
+        quote!(<#args as ::moonbase::extract::TryExtractFrom<#context>>::try_extract_from(&context).await?),
+    );
+    let vis = &function.vis;
+    let after = &options.after;
+    let provides = &options.provides;
+    Ok(quote! {
+        #function
+        #vis struct #name;
+        #definition
+        #extract
+        impl ::moonbase::module::Module<#context> for #name {
+            fn dependencies() -> ::std::vec::Vec<::moonbase::module::Dependency> {
+                let mut dependencies =
+                    <#args as ::moonbase::extract::TryExtractFrom<#context>>::requirements();
+                #(dependencies.push(::moonbase::module::Dependency::module::<#after>());)*
+                dependencies
+            }
+            fn provides() -> ::std::vec::Vec<::moonbase::module::Dependency> {
+                ::std::vec![#(::moonbase::module::Dependency::resource::<#provides>(),)*]
+            }
+            async fn initialize(self, context: #context) -> ::moonbase::__private::anyhow::Result<()> {
+                ::moonbase::__private::IntoInitResult::into_init_result(#call)
+            }
+        }
+    })
+}
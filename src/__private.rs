@@ -0,0 +1,112 @@
+//! Used by the code generated by `moonbase-macros`, not a public API.
+
+use anyhow::Context as _;
+
+use crate::{
+    components::{ComponentName, MoonbaseComponent},
+    context::Context,
+    daemon::DaemonOutcome,
+    extract::{ExtractFrom, TryExtractFrom, TryExtractTuple},
+    resource::MoonbaseResource,
+    Moonbase,
+};
+
+pub use anyhow;
+
+pub async fn try_extract_field<C, T>(
+    context: &C,
+    owner: &'static str,
+    field: &'static str,
+) -> anyhow::Result<T>
+where
+    C: Context,
+    T: TryExtractFrom<C>,
+{
+    let value = T::try_extract_from(context).await;
+    value
+        .map_err(crate::extract::into_anyhow)
+        .with_context(|| format!("fail to extract field `{field}` of `{owner}`"))
+}
+
+pub async fn try_extract_tuple_field<C, T>(
+    context: &C,
+    owner: &'static str,
+    field: &'static str,
+) -> anyhow::Result<T>
+where
+    C: Context,
+    T: TryExtractTuple<C>,
+{
+    let value = T::try_extract_tuple(context).await;
+    value.with_context(|| format!("fail to extract field `{field}` of `{owner}`"))
+}
+
+pub async fn extract_resource<C, T>(
+    context: &C,
+    owner: &'static str,
+    field: &'static str,
+) -> anyhow::Result<T>
+where
+    C: Context,
+    T: MoonbaseResource,
+    Moonbase: ExtractFrom<C>,
+{
+    let moonbase = Moonbase::extract_from(context).await;
+    let resource = moonbase.get_or_init_resource::<T>().await;
+    resource.with_context(|| format!("fail to extract field `{field}` of `{owner}`"))
+}
+
+pub async fn extract_component<C, T>(
+    context: &C,
+    name: &'static str,
+    owner: &'static str,
+    field: &'static str,
+) -> anyhow::Result<T>
+where
+    C: Context,
+    T: MoonbaseComponent,
+    Moonbase: ExtractFrom<C>,
+{
+    let moonbase = Moonbase::extract_from(context).await;
+    let component = moonbase.get_component::<T>(&ComponentName::new(name));
+    component
+        .with_context(|| format!("component `{name}` not found"))
+        .with_context(|| format!("fail to extract field `{field}` of `{owner}`"))
+}
+
+/// What a fn under `#[daemon]` returns.
+pub trait IntoDaemonOutcome {
+    fn into_daemon_outcome<D>(self) -> DaemonOutcome<D>;
+}
+
+impl IntoDaemonOutcome for () {
+    fn into_daemon_outcome<D>(self) -> DaemonOutcome<D> {
+        DaemonOutcome::Stop
+    }
+}
+
+impl<E: Into<anyhow::Error>> IntoDaemonOutcome for Result<(), E> {
+    fn into_daemon_outcome<D>(self) -> DaemonOutcome<D> {
+        match self {
+            Ok(()) => DaemonOutcome::Stop,
+            Err(error) => DaemonOutcome::failed(error),
+        }
+    }
+}
+
+/// What a fn under `#[module]` returns.
+pub trait IntoInitResult {
+    fn into_init_result(self) -> anyhow::Result<()>;
+}
+
+impl IntoInitResult for () {
+    fn into_init_result(self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl<E: Into<anyhow::Error>> IntoInitResult for Result<(), E> {
+    fn into_init_result(self) -> anyhow::Result<()> {
+        self.map_err(Into::into)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{
+    module::{Module, ModuleGraph, ModuleLoadError},
+    resource::MoonbaseResource,
+    Moonbase,
+};
+
+/// Build a [`Moonbase`] from a set of resources and modules.
+///
+/// Modules are loaded in the order of their [dependencies](Module::dependencies) rather than
+/// the order they are added.
+#[derive(Debug, Default)]
+pub struct MoonbaseBuilder {
+    moonbase: Moonbase,
+    modules: ModuleGraph,
+}
+
+impl MoonbaseBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn resource<T: MoonbaseResource>(self, resource: T) -> Self {
+        self.moonbase.set_resource(resource);
+        self
+    }
+    pub fn module<M: Module<Moonbase>>(mut self, module: M) -> Self {
+        self.modules.add(module);
+        self
+    }
+    pub async fn build(self) -> Result<Moonbase, ModuleLoadError> {
+        self.modules.load(&self.moonbase).await?;
+        Ok(self.moonbase)
+    }
+}
+
+impl Moonbase {
+    pub fn builder() -> MoonbaseBuilder {
+        MoonbaseBuilder::new()
+    }
+}
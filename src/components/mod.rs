@@ -11,11 +11,15 @@ pub struct Entity {
 pub trait MoonbaseComponent: Any + Clone + Send + Sync + 'static {}
 #[derive(Debug, Default)]
 pub struct ComponentRepositoryInner {
-    components: HashMap<u64, Box<dyn Any + Send + Sync>>,
+    components: HashMap<u64, Arc<dyn Any + Send + Sync>>,
 }
 
 pub type ComponentRepository = Arc<ShardedLock<ComponentRepositoryInner>>;
 
+fn downcast<T: Any + Send + Sync>(component: Arc<dyn Any + Send + Sync>) -> Arc<T> {
+    Arc::downcast(component).expect("type mismatch")
+}
+
 impl ComponentRepositoryInner {
     pub fn new() -> Self {
         ComponentRepositoryInner::default()
@@ -26,62 +30,95 @@ impl ComponentRepositoryInner {
         name: &ComponentName<T>,
         component: T,
     ) -> Option<T> {
-        let id = name.hash();
-        let replaced = self.components.insert(id, Box::new(component));
-        replaced.map(|component| *component.downcast::<T>().expect("type mismatch").clone())
+        self.insert_arc(name, Arc::new(component))
+            .map(Arc::unwrap_or_clone)
+    }
+
+    pub fn insert_arc<T: Any + Send + Sync>(
+        &mut self,
+        name: &ComponentName<T>,
+        component: Arc<T>,
+    ) -> Option<Arc<T>> {
+        self.components.insert(name.hash(), component).map(downcast)
     }
 
     pub fn remove<T: MoonbaseComponent>(&mut self, name: &ComponentName<T>) -> Option<T> {
-        let id = name.hash();
-        self.components
-            .remove(&id)
-            .map(|component| *component.downcast::<T>().expect("type mismatch").clone())
+        self.remove_arc(name).map(Arc::unwrap_or_clone)
+    }
+
+    pub fn remove_arc<T: Any + Send + Sync>(&mut self, name: &ComponentName<T>) -> Option<Arc<T>> {
+        self.components.remove(&name.hash()).map(downcast)
     }
 
     pub fn get<T: MoonbaseComponent>(&self, name: &ComponentName<T>) -> Option<T> {
-        let id = name.hash();
-        self.components.get(&id).map(|component| {
-            component
-                .downcast_ref::<T>()
-                .expect("type mismatch")
-                .clone()
-        })
+        self.get_arc(name).map(|component| T::clone(&component))
+    }
+
+    pub fn get_arc<T: Any + Send + Sync>(&self, name: &ComponentName<T>) -> Option<Arc<T>> {
+        self.components.get(&name.hash()).cloned().map(downcast)
     }
 
     pub fn iter<T: MoonbaseComponent>(&self) -> impl Iterator<Item = T> + '_ {
+        self.iter_arc::<T>().map(Arc::unwrap_or_clone)
+    }
+
+    pub fn iter_arc<T: Any + Send + Sync>(&self) -> impl Iterator<Item = Arc<T>> + '_ {
         self.components
             .values()
-            .filter_map(|component| component.downcast_ref::<T>().cloned())
+            .filter(|component| component.is::<T>())
+            .cloned()
+            .map(downcast)
     }
 }
 
-
-
 impl Moonbase {
     pub fn set_component<T: MoonbaseComponent>(
         &self,
         name: &ComponentName<T>,
         component: T,
+    ) {
+        self.set_component_arc(name, Arc::new(component));
+    }
+    /// Set a component already shared, or which is not [`Clone`].
+    pub fn set_component_arc<T: Any + Send + Sync>(
+        &self,
+        name: &ComponentName<T>,
+        component: Arc<T>,
     ) {
         let mut components = self.components.write().unwrap();
-        components.insert(name, component);
+        components.insert_arc(name, component);
     }
+    /// Falls back to the parent context if it's not set here.
     pub fn get_component<T: MoonbaseComponent>(
         &self,
         name: &ComponentName<T>,
     ) -> Option<T> {
-        let components = self.components.read().unwrap();
-        components.get(name)
+        self.get_component_arc(name)
+            .map(|component| T::clone(&component))
     }
+    /// Same as [`get_component`](Moonbase::get_component), without cloning the component.
+    pub fn get_component_arc<T: Any + Send + Sync>(
+        &self,
+        name: &ComponentName<T>,
+    ) -> Option<Arc<T>> {
+        let component = self.components.read().unwrap().get_arc(name);
+        component.or_else(|| self.parent()?.get_component_arc(name))
+    }
+    /// Only removes the component set in this context.
     pub fn remove_component<T: MoonbaseComponent>(
         &self,
         name: &ComponentName<T>,
     ) -> Option<T> {
+        self.remove_component_arc(name).map(Arc::unwrap_or_clone)
+    }
+    pub fn remove_component_arc<T: Any + Send + Sync>(
+        &self,
+        name: &ComponentName<T>,
+    ) -> Option<Arc<T>> {
         let mut components = self.components.write().unwrap();
-        components.remove(name)
+        components.remove_arc(name)
     }
-    pub fn has_component<T: MoonbaseComponent>(&self, name: &ComponentName<T>) -> bool {
-        let components = self.components.read().unwrap();
-        components.get(name).is_some()
+    pub fn has_component<T: Any + Send + Sync>(&self, name: &ComponentName<T>) -> bool {
+        self.get_component_arc(name).is_some()
     }
 }
@@ -89,6 +89,20 @@ impl<T: Any> ComponentName<T> {
             ComponentDomain::DaemonHandle,
         )
     }
+    /// Name of a daemon instance, keyed by the daemon type plus the instance name.
+    pub fn new_daemon_instance<D, C>(instance: &str) -> Self
+    where
+        C: Context,
+        D: Daemon<C>,
+    {
+        let mut bytes = crate::utils::hash(&TypeId::of::<D>())
+            .to_be_bytes()
+            .to_vec();
+        bytes.push(b'#');
+        bytes.extend_from_slice(instance.as_bytes());
+        let name = format!("{}#{}", std::any::type_name::<D>(), instance);
+        Self::new_with_domain(name, bytes, ComponentDomain::DaemonHandle)
+    }
     pub fn new(id: impl Into<Cow<'static, str>>) -> Self {
         let name = id.into();
         let bytes = name.as_bytes().to_vec();
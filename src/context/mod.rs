@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 use futures::Future;
 
 use crate::{
-    extract::{ExtractFrom, TryExtractFrom},
+    extract::{ExtractFrom, TryExtractFrom, TryExtractTuple},
     handler::{Adapter, Call, Handler},
     module::Module,
 };
@@ -17,11 +17,19 @@ pub trait Context: Sized + Send + Sync + 'static {
     {
         async move { handler.apply(self.extract().await).await }
     }
+    /// Extract a value, infallible extractors never fail while fallible ones, e.g. a
+    /// [`Resource`](crate::resource::Resource), panic with their error.
+    ///
+    /// Use [`Context::try_extract`] to handle the error instead.
     fn extract<T>(&self) -> impl Future<Output = T> + Send
     where
-        T: ExtractFrom<Self>,
+        T: TryExtractFrom<Self>,
     {
-        T::extract_from(self)
+        async move {
+            T::try_extract_from(self).await.unwrap_or_else(|error| {
+                panic!("fail to extract `{}`: {error}", std::any::type_name::<T>())
+            })
+        }
     }
     fn try_extract<T>(&self) -> impl Future<Output = Result<T, T::Error>> + Send
     where
@@ -29,20 +37,60 @@ pub trait Context: Sized + Send + Sync + 'static {
     {
         T::try_extract_from(self)
     }
+    /// Called before [`ContextExt::load_module`] initializes a module, an error rejects it.
+    fn module_loading<M: Module<Self>>(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+    /// Called once [`ContextExt::load_module`] has initialized a module, so the context can
+    /// finalize it later.
+    fn module_loaded<M: Module<Self>>(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 pub trait ContextExt: Context {
     /// Call a fallible function with the context.
     ///
-    /// This is a convenience method that calls the [`Context::call_handler`] method on the handler,
-    /// with a [`Call`] adapter.
+    /// Arguments are extracted like [`Context::extract`], so it panics if a fallible one fails,
+    /// see [`ContextExt::try_call`] to handle the error instead.
     fn call<T, R, H>(&self, handler: H) -> impl Future<Output = R::Output>
     where
         H: Handler<Call<T, R>> + Send,
-        T: ExtractFrom<Self>,
+        T: TryExtractTuple<Self> + Send,
         R: Future,
     {
-        self.call_handler(handler)
+        async move {
+            let args = T::try_extract_tuple(self)
+                .await
+                .unwrap_or_else(|error| panic!("{error:#}"));
+            handler.apply(args).await
+        }
+    }
+
+    /// Extract a tuple mixing fallible extractors, e.g. `(Resource<Db>, Moonbase)`, which is a
+    /// [`TryExtractTuple`] but not a [`TryExtractFrom`].
+    ///
+    /// The error tells which element fails.
+    fn try_extract_tuple<T>(&self) -> impl Future<Output = anyhow::Result<T>> + Send
+    where
+        T: TryExtractTuple<Self>,
+    {
+        T::try_extract_tuple(self)
+    }
+
+    /// Call a function whose arguments may fail to extract, e.g. a [`Resource`](crate::resource::Resource).
+    ///
+    /// The handler isn't called if any argument fails, and the error tells which one.
+    fn try_call<T, R, H>(&self, handler: H) -> impl Future<Output = anyhow::Result<R::Output>>
+    where
+        H: Handler<Call<T, R>> + Send,
+        T: TryExtractTuple<Self> + Send,
+        R: Future,
+    {
+        async move {
+            let args = T::try_extract_tuple(self).await?;
+            Ok(handler.apply(args).await)
+        }
     }
 
     // /// Call an infallible function with the context.
@@ -60,16 +108,22 @@ pub trait ContextExt: Context {
     // }
 
     /// Load a module into the context.
-
+    ///
     /// This is a convenience method that calls the [`Context::call`] method on the module,
     /// with a [`ModuleAdapter`](`crate::module::ModuleAdapter`) adapter,
-    /// in witch [`Module::initialize`] is called.
+    /// in witch [`Module::initialize`] is called, between [`Context::module_loading`] and
+    /// [`Context::module_loaded`].
     fn load_module<M>(&self, module: M) -> impl Future<Output = anyhow::Result<()>>
     where
         M: Module<Self> + Send,
         Self: ExtractFrom<Self>,
     {
-        self.call_handler::<crate::module::ModuleAdapter<M, Self>, M>(module)
+        async move {
+            self.module_loading::<M>()?;
+            self.call_handler::<crate::module::ModuleAdapter<M, Self>, M>(module)
+                .await?;
+            self.module_loaded::<M>()
+        }
     }
 }
 
@@ -99,6 +153,9 @@ where
     async fn extract_from(context: &S) -> Self {
         FromContext::new(T::extract_from(context.as_ref()).await)
     }
+    fn requirements() -> Vec<crate::module::Dependency> {
+        T::requirements()
+    }
 }
 
 pub type FromMoonbase<T> = FromContext<T, crate::Moonbase>;
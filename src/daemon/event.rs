@@ -0,0 +1,74 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use futures::channel::mpsc;
+
+use crate::Moonbase;
+
+use super::{DaemonError, DaemonStatus};
+
+/// A lifecycle transition of a daemon, see [`Moonbase::daemon_events`].
+#[derive(Debug, Clone)]
+pub struct DaemonEvent {
+    /// the daemon name, same as [`DaemonHandle::name`](super::DaemonHandle::name)
+    pub name: String,
+    pub kind: DaemonEventKind,
+    pub occurred_at: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+pub enum DaemonEventKind {
+    /// the daemon is preparing a run
+    Starting,
+    /// a run started
+    Running,
+    /// the run is paused
+    Paused,
+    /// a run failed, with an error, a fatal error or a panic
+    Failed(DaemonError),
+    /// waited the backoff delay before restarting, not published without a delay
+    CooledDown(Duration),
+    /// a new run is prepared, with the total restarted times
+    Restarted(usize),
+    /// the daemon won't run anymore, with its final status
+    Terminated(DaemonStatus),
+}
+
+pub type DaemonEventStream = mpsc::UnboundedReceiver<DaemonEvent>;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DaemonEventBus {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<DaemonEvent>>>>,
+}
+
+impl DaemonEventBus {
+    pub(crate) fn subscribe(&self) -> DaemonEventStream {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+    pub(crate) fn publish(&self, name: &str, kind: DaemonEventKind) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+        let event = DaemonEvent {
+            name: name.to_owned(),
+            kind,
+            occurred_at: SystemTime::now(),
+        };
+        // dropped streams are removed here
+        subscribers.retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+impl Moonbase {
+    /// Subscribe to lifecycle events of every daemon, from now on.
+    ///
+    /// The stream never ends by itself, drop it to unsubscribe.
+    pub fn daemon_events(&self) -> DaemonEventStream {
+        self.daemon_events.subscribe()
+    }
+}
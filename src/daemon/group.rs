@@ -0,0 +1,237 @@
+use std::{
+    any::TypeId,
+    borrow::Cow,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures::future::BoxFuture;
+
+use crate::{signal::Signal, Moonbase};
+
+use super::{extract_daemon, Daemon, DaemonHandle};
+
+/// Which members of a [`DaemonGroup`] are restarted when one of them exits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GroupStrategy {
+    /// only the exited daemon is restarted
+    #[default]
+    OneForOne,
+    /// every daemon in the group is restarted
+    OneForAll,
+    /// the exited daemon and those added after it are restarted
+    RestForOne,
+}
+
+impl GroupStrategy {
+    fn affects(&self, member: usize, origin: usize) -> bool {
+        match self {
+            GroupStrategy::OneForOne => false,
+            GroupStrategy::OneForAll => member != origin,
+            GroupStrategy::RestForOne => member > origin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GroupEventKind {
+    /// the origin is restarting, affected members should stop and wait for it
+    Restart,
+    /// the origin gave up, affected members should terminate as well
+    Terminate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GroupEvent {
+    pub(crate) origin: usize,
+    pub(crate) kind: GroupEventKind,
+    /// unique in the group, the members may start again once the origin is restarted after it
+    pub(crate) generation: u64,
+}
+
+/// The events affecting a member, queued until it's ready to handle them.
+#[derive(Debug, Default)]
+struct MemberQueue {
+    events: Mutex<VecDeque<GroupEvent>>,
+    notify: Signal,
+    /// the generation of the last event this member is restarted after
+    restarted: AtomicU64,
+}
+
+#[derive(Debug)]
+struct GroupShared {
+    strategy: GroupStrategy,
+    generation: AtomicU64,
+    members: Vec<MemberQueue>,
+    up: Signal,
+}
+
+/// The membership of a supervised daemon in a group.
+#[derive(Debug, Clone)]
+pub(crate) struct GroupLink {
+    index: usize,
+    shared: Arc<GroupShared>,
+}
+
+impl GroupLink {
+    /// Resolves with the next group event affecting this member.
+    pub(crate) async fn affected(&self) -> GroupEvent {
+        let member = &self.shared.members[self.index];
+        loop {
+            let notified = member.notify.recv();
+            if let Some(event) = member.events.lock().unwrap().pop_front() {
+                return event;
+            }
+            notified.await;
+        }
+    }
+    /// Notify the group that this member exited, and will restart unless `kind` is terminate.
+    pub(crate) fn exited(&self, kind: GroupEventKind) -> GroupEvent {
+        let generation = self.shared.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let event = GroupEvent {
+            origin: self.index,
+            kind,
+            generation,
+        };
+        for (index, member) in self.shared.members.iter().enumerate() {
+            if self.shared.strategy.affects(index, self.index) {
+                member.events.lock().unwrap().push_back(event);
+                member.notify.get_sender().send();
+            }
+        }
+        event
+    }
+    /// Notify the group that this member is started again after `event`.
+    pub(crate) fn restarted(&self, event: GroupEvent) {
+        self.shared.members[self.index]
+            .restarted
+            .fetch_max(event.generation, Ordering::SeqCst);
+        self.shared.up.get_sender().send();
+    }
+    /// Resolves when the origin of `event` is restarted.
+    pub(crate) async fn wait_restarted(&self, event: GroupEvent) {
+        let origin = &self.shared.members[event.origin];
+        loop {
+            let up = self.shared.up.recv();
+            if origin.restarted.load(Ordering::SeqCst) >= event.generation {
+                return;
+            }
+            up.await;
+        }
+    }
+}
+
+type MemberSpawner =
+    Box<dyn FnOnce(Moonbase, GroupLink) -> BoxFuture<'static, anyhow::Result<DaemonHandle>> + Send>;
+
+struct Member {
+    daemon_type: TypeId,
+    daemon_name: &'static str,
+    spawn: MemberSpawner,
+}
+
+/// A set of daemons supervised together, see [`GroupStrategy`].
+///
+/// Members are keyed by their type like [`Moonbase::run_daemon`], so a group holds at most one
+/// daemon of each type, and replaces the daemon of that type run outside of the group.
+pub struct DaemonGroup {
+    context: Moonbase,
+    name: Cow<'static, str>,
+    strategy: GroupStrategy,
+    members: Vec<Member>,
+}
+
+impl std::fmt::Debug for DaemonGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DaemonGroup")
+            .field("name", &self.name)
+            .field("strategy", &self.strategy)
+            .field("members", &self.members.len())
+            .finish()
+    }
+}
+
+impl DaemonGroup {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn strategy(mut self, strategy: GroupStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+    /// Add a daemon to the group, members are ordered as they are added.
+    pub fn daemon<D>(mut self) -> Self
+    where
+        D: Daemon<Moonbase>,
+        D::IntoFuture: Send + 'static,
+    {
+        self.members.push(Member {
+            daemon_type: TypeId::of::<D>(),
+            daemon_name: std::any::type_name::<D>(),
+            spawn: Box::new(|context, link| {
+                Box::pin(async move {
+                    context
+                        .spawn_daemon(None, extract_daemon::<D>(), Some(link))
+                        .await
+                })
+            }),
+        });
+        self
+    }
+    /// Run every member in order, returns their handles in the same order.
+    ///
+    /// Fails if a daemon type is added more than once, or if a member fails to start, then the
+    /// members already started are killed.
+    pub async fn run(self) -> anyhow::Result<Vec<DaemonHandle>> {
+        for (index, member) in self.members.iter().enumerate() {
+            anyhow::ensure!(
+                self.members[..index]
+                    .iter()
+                    .all(|added| added.daemon_type != member.daemon_type),
+                "daemon `{}` is added to group `{}` more than once",
+                member.daemon_name,
+                self.name
+            );
+        }
+        let shared = Arc::new(GroupShared {
+            strategy: self.strategy,
+            generation: AtomicU64::new(0),
+            members: self.members.iter().map(|_| Default::default()).collect(),
+            up: Signal::new(),
+        });
+        let mut handles = Vec::with_capacity(self.members.len());
+        for (index, member) in self.members.into_iter().enumerate() {
+            let link = GroupLink {
+                index,
+                shared: shared.clone(),
+            };
+            match (member.spawn)(self.context.clone(), link).await {
+                Ok(handle) => handles.push(handle),
+                Err(error) => {
+                    // the members started are linked to a group which won't be complete
+                    for handle in &handles {
+                        handle.kill_guard_and_wait().await;
+                    }
+                    let context = format!("fail to run daemon group `{}`", self.name);
+                    return Err(error.context(context));
+                }
+            }
+        }
+        Ok(handles)
+    }
+}
+
+impl Moonbase {
+    /// Create a group of daemons, see [`DaemonGroup`].
+    pub fn daemon_group(&self, name: impl Into<Cow<'static, str>>) -> DaemonGroup {
+        DaemonGroup {
+            context: self.clone(),
+            name: name.into(),
+            strategy: GroupStrategy::default(),
+            members: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::{future::Future, sync::Arc};
+
+use crate::{components::ComponentName, Moonbase};
+
+use super::{extract_daemon, Daemon, DaemonHandle};
+
+impl Moonbase {
+    /// Run an instance of daemon `D` extracted from context, keyed by its type plus `instance`.
+    pub async fn run_daemon_instance<D>(
+        &self,
+        instance: impl Into<Arc<str>>,
+    ) -> anyhow::Result<DaemonHandle>
+    where
+        D: Daemon<Self>,
+        D::IntoFuture: Send + 'static,
+    {
+        self.spawn_daemon(Some(instance.into()), extract_daemon::<D>(), None)
+            .await
+    }
+    /// Run an instance of daemon `D` built by `factory`, e.g. from a config value.
+    ///
+    /// The factory is called again to rebuild the daemon after a failed run.
+    pub async fn run_daemon_instance_with<D, F, Fut>(
+        &self,
+        instance: impl Into<Arc<str>>,
+        factory: F,
+    ) -> anyhow::Result<DaemonHandle>
+    where
+        D: Daemon<Self>,
+        D::IntoFuture: Send + 'static,
+        F: Fn(Moonbase) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<D>> + Send + 'static,
+    {
+        let factory = Arc::new(move |context| Box::pin(factory(context)) as _);
+        self.spawn_daemon(Some(instance.into()), factory, None)
+            .await
+    }
+    pub fn get_daemon_instance<D>(&self, instance: &str) -> Option<DaemonHandle>
+    where
+        D: Daemon<Self>,
+    {
+        let handler_name = ComponentName::new_daemon_instance::<D, Self>(instance);
+        self.get_component(&handler_name)
+    }
+    /// Handles of every instance of daemon `D`, sorted by instance name.
+    pub fn daemon_instances<D>(&self) -> Vec<DaemonHandle>
+    where
+        D: Daemon<Self>,
+    {
+        let mut handles = self
+            .daemon_handles()
+            .into_iter()
+            .filter(|handle| handle.is::<D>() && handle.instance().is_some())
+            .collect::<Vec<_>>();
+        handles.sort_by(|a, b| a.instance().cmp(&b.instance()));
+        handles
+    }
+    /// Kill an instance of daemon `D` and wait until it stops, returns its handle if found.
+    pub async fn kill_daemon_instance<D>(&self, instance: &str) -> Option<DaemonHandle>
+    where
+        D: Daemon<Self>,
+    {
+        let handle = self.get_daemon_instance::<D>(instance)?;
+        handle.kill_guard_and_wait().await;
+        Some(handle)
+    }
+}
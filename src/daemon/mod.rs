@@ -1,27 +1,68 @@
 use std::{
     any::{Any, TypeId},
     future::IntoFuture,
+    panic::AssertUnwindSafe,
     sync::{atomic::AtomicUsize, Arc},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+mod event;
+mod group;
+mod instance;
+mod outcome;
+mod pause;
+mod policy;
+mod supervisor;
+pub use event::*;
+pub use group::*;
+pub use outcome::*;
+pub use policy::*;
+use pause::PauseControl;
+use supervisor::Supervisor;
+
 use crossbeam::atomic::AtomicCell;
-use futures::FutureExt;
+use futures::{
+    channel::oneshot,
+    future::{join_all, BoxFuture, Shared},
+    FutureExt,
+};
 
 use crate::{
     components::{ComponentName, MoonbaseComponent},
     context::Context,
-    extract::TryExtractFrom,
-    runtime::Runtime,
+    extract::{into_anyhow, TryExtractFrom},
+    runtime::{Runtime, RuntimeHandle},
     Moonbase,
 };
 
+/// Builds the daemon for its first run, and again after a failed run.
+pub(crate) type DaemonFactory<D> =
+    Arc<dyn Fn(Moonbase) -> BoxFuture<'static, anyhow::Result<D>> + Send + Sync>;
+
+/// The factory of daemons extracted from context.
+pub(crate) fn extract_daemon<D>() -> DaemonFactory<D>
+where
+    D: Daemon<Moonbase>,
+{
+    Arc::new(|context| {
+        Box::pin(async move {
+            let daemon = D::try_extract_from(&context).await.map_err(into_anyhow);
+            anyhow::Context::context(daemon, "fail to extract daemon")
+        })
+    })
+}
+
 pub struct DaemonDescriptor {
     pub type_id: TypeId,
 }
 
 pub trait Daemon<C>:
-    IntoFuture<Output = Self> + TryExtractFrom<C> + Send + 'static + Any + std::fmt::Debug
+    IntoFuture<Output: Into<DaemonOutcome<Self>> + Send>
+    + TryExtractFrom<C>
+    + Send
+    + 'static
+    + Any
+    + std::fmt::Debug
 where
     C: Context,
 {
@@ -36,36 +77,124 @@ where
     fn cool_down_time(&self) -> Option<Duration> {
         None
     }
+    /// How the daemon is restarted, by default built from
+    /// [`max_restart_time`](Daemon::max_restart_time) and [`cool_down_time`](Daemon::cool_down_time).
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts: self.max_restart_time(),
+            backoff: self.cool_down_time().map(Backoff::Fixed),
+            intensity: None,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum DaemonStatus {
     Starting,
     Running,
+    /// the run is not polled until resumed
+    Paused,
+    /// stopped, killed or gave up restarting after normal runs
     Terminated,
+    /// stopped by a fatal error, or gave up restarting after errors
+    Failed,
+    /// gave up restarting after a panic
+    Panicked,
+}
+
+impl DaemonStatus {
+    /// Whether the daemon won't run anymore.
+    pub fn is_terminated(&self) -> bool {
+        matches!(
+            self,
+            DaemonStatus::Terminated | DaemonStatus::Failed | DaemonStatus::Panicked
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct DaemonHandle {
-    pub(crate) kill: Arc<std::sync::Mutex<Option<futures::channel::oneshot::Sender<()>>>>,
-    pub(crate) finish: Arc<std::sync::Mutex<Option<futures::channel::oneshot::Receiver<()>>>>,
+    pub(crate) name: ComponentName<DaemonHandle>,
+    pub(crate) daemon_type: TypeId,
+    pub(crate) instance: Option<Arc<str>>,
+    pub(crate) kill: Arc<std::sync::Mutex<Option<oneshot::Sender<()>>>>,
+    pub(crate) finish: Shared<oneshot::Receiver<()>>,
     pub(crate) state: Arc<crossbeam::atomic::AtomicCell<DaemonStatus>>,
     pub(crate) is_guarded: bool,
     pub(crate) restarted: Arc<AtomicUsize>,
+    pub(crate) last_error: Arc<std::sync::Mutex<Option<DaemonError>>>,
+    pub(crate) running_since: Arc<AtomicCell<Option<Instant>>>,
+    pub(crate) pause: Arc<PauseControl>,
+    /// the clock of `running_since`
+    pub(crate) runtime: RuntimeHandle,
+}
+
+/// A snapshot of a daemon, see [`Moonbase::daemons`].
+#[derive(Debug, Clone)]
+pub struct DaemonInfo {
+    pub name: String,
+    pub instance: Option<String>,
+    pub status: DaemonStatus,
+    pub restarted_times: usize,
+    /// how long the daemon runs since started or resumed, `None` if it's not running
+    pub uptime: Option<Duration>,
+    pub last_error: Option<DaemonError>,
+    pub is_guarded: bool,
 }
 
 impl MoonbaseComponent for DaemonHandle {}
 
+/// Daemons run in child contexts, so the root can stop them on shutdown.
+pub(crate) type ChildDaemons = Arc<std::sync::Mutex<Vec<DaemonHandle>>>;
+
 impl DaemonHandle {
+    pub fn name(&self) -> &str {
+        self.name.readable_name()
+    }
+    /// The instance name if it's run by [`Moonbase::run_daemon_instance`].
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+    /// Whether this is a handle of daemon `D`.
+    pub fn is<D: Any>(&self) -> bool {
+        self.daemon_type == TypeId::of::<D>()
+    }
     pub fn restarted_times(&self) -> usize {
         self.restarted.load(std::sync::atomic::Ordering::Relaxed)
     }
     pub fn state(&self) -> DaemonStatus {
         self.state.load()
     }
+    /// The error of the last failed run, kept after later successful runs.
+    pub fn last_error(&self) -> Option<DaemonError> {
+        self.last_error.lock().unwrap().clone()
+    }
     pub fn is_guarded(&self) -> bool {
         self.is_guarded
     }
+    /// How long the current run lasts since started or resumed, `None` if it's not running.
+    ///
+    /// Measured by the runtime's clock, so it follows a virtual clock in tests.
+    pub fn uptime(&self) -> Option<Duration> {
+        match self.state() {
+            DaemonStatus::Running => self
+                .running_since
+                .load()
+                .map(|since| self.runtime.now().saturating_duration_since(since)),
+            _ => None,
+        }
+    }
+    pub fn info(&self) -> DaemonInfo {
+        DaemonInfo {
+            name: self.name().to_owned(),
+            instance: self.instance().map(str::to_owned),
+            status: self.state(),
+            restarted_times: self.restarted_times(),
+            uptime: self.uptime(),
+            last_error: self.last_error(),
+            is_guarded: self.is_guarded,
+        }
+    }
     pub fn kill_guard(&self) {
         let mut kill = self.kill.lock().unwrap();
         if let Some(kill) = kill.take() {
@@ -73,10 +202,7 @@ impl DaemonHandle {
         }
     }
     pub async fn wait(&self) {
-        let finish = self.finish.lock().unwrap().take();
-        if let Some(finish) = finish {
-            let _ = finish.await;
-        }
+        let _ = self.finish.clone().await;
     }
     pub async fn kill_guard_and_wait(&self) {
         self.kill_guard();
@@ -89,7 +215,7 @@ impl Drop for DaemonHandle {
         if Arc::strong_count(&self.kill) > 1 {
             return;
         }
-        if self.state.load() != DaemonStatus::Terminated {
+        if !self.state.load().is_terminated() {
             self.kill_guard();
         }
     }
@@ -100,68 +226,90 @@ impl Moonbase {
     where
         D: Daemon<Self>,
         D::IntoFuture: Send + 'static,
-        <D as TryExtractFrom<Moonbase>>::Error: std::error::Error,
     {
+        self.spawn_daemon::<D>(None, extract_daemon(), None).await
+    }
+    pub(crate) async fn spawn_daemon<D>(
+        &self,
+        instance: Option<Arc<str>>,
+        factory: DaemonFactory<D>,
+        link: Option<GroupLink>,
+    ) -> anyhow::Result<DaemonHandle>
+    where
+        D: Daemon<Self>,
+        D::IntoFuture: Send + 'static,
+    {
+        let runtime = self.runtime()?;
         // prepare the daemon
-        let handler_name = ComponentName::new_daemon_handle::<D, Self>();
-        // fetch prev handle
-        if let Some(prev_handle) = self.get_component::<DaemonHandle>(&handler_name) {
+        let handler_name = match &instance {
+            Some(instance) => ComponentName::new_daemon_instance::<D, Self>(instance),
+            None => ComponentName::new_daemon_handle::<D, Self>(),
+        };
+        // fetch prev handle, a daemon of the parent is not replaced by the child's
+        let prev_handle = self
+            .components
+            .read()
+            .unwrap()
+            .get::<DaemonHandle>(&handler_name);
+        if let Some(prev_handle) = prev_handle {
             anyhow::ensure!(
-                !prev_handle.is_guarded,
-                "daemon {:?} is still running",
-                &self
+                !prev_handle.is_guarded || prev_handle.state().is_terminated(),
+                "daemon {} is still running",
+                prev_handle.name()
             );
             prev_handle.kill_guard_and_wait().await;
             self.remove_component(&handler_name);
         }
-        let daemon =
-            anyhow::Context::context(D::try_extract_from(self).await, "fail to extract daemon")?;
-        let max_restart_time = daemon.max_restart_time();
-        let cool_down_time = daemon.cool_down_time();
-        let (finish_tx, finish_rx) = futures::channel::oneshot::channel::<()>();
-        let (kill_tx, mut kill_rx) = futures::channel::oneshot::channel::<()>();
-        let runtime = self
-            .get_resource::<crate::runtime::DefaultRuntime>()
-            .expect("no runtime found");
+        let daemon = factory(self.clone()).await?;
+        let policy = daemon.restart_policy();
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
         let status = Arc::new(AtomicCell::new(DaemonStatus::Starting));
-        let restart_time = Arc::new(AtomicUsize::new(0));
+        let restarted = Arc::new(AtomicUsize::new(0));
+        let last_error = Arc::new(std::sync::Mutex::new(None));
+        let running_since = Arc::new(AtomicCell::new(None));
+        let pause = Arc::new(PauseControl::default());
         let handle = DaemonHandle {
+            name: handler_name.clone(),
+            daemon_type: TypeId::of::<D>(),
+            instance,
             kill: Arc::new(std::sync::Mutex::new(Some(kill_tx))),
-            finish: Arc::new(std::sync::Mutex::new(Some(finish_rx))),
+            finish: finish_rx.shared(),
             state: status.clone(),
-            is_guarded: max_restart_time != Some(0),
-            restarted: restart_time.clone(),
+            is_guarded: policy.max_restarts != Some(0),
+            restarted: restarted.clone(),
+            last_error: last_error.clone(),
+            running_since: running_since.clone(),
+            pause: pause.clone(),
+            runtime: runtime.clone(),
         };
-        crate::runtime::Runtime::spawn(&runtime.clone(), async move {
-            let status = status.clone();
-            let restart_time = restart_time.clone();
-            let mut daemon = daemon;
-            loop {
-                let current_time = restart_time.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-                if let Some(max_restart_time) = max_restart_time {
-                    if current_time > max_restart_time {
-                        status.store(DaemonStatus::Terminated);
-                        break;
-                    }
-                }
-                status.store(DaemonStatus::Running);
-                futures::select! {
-                    next_daemon = daemon.into_future().fuse() => {
-                        daemon = next_daemon;
-                        status.store(DaemonStatus::Starting);
-                        if let Some(cool_down_time) = cool_down_time {
-                            let _ = runtime.sleep(cool_down_time).await;
-                        }
-                    }
-                    _ = kill_rx => {
-                        status.store(DaemonStatus::Terminated);
-                        break;
-                    }
-                };
+        let mut supervisor = Supervisor {
+            name: handle.name().to_owned(),
+            events: self.daemon_events.clone(),
+            context: self.clone(),
+            runtime: runtime.clone(),
+            tracker: policy.tracker(),
+            status,
+            restarted,
+            last_error,
+            running_since,
+            pause,
+            link,
+        };
+        runtime.spawn_detached(async move {
+            // runs are isolated, this only catches panics of the supervisor itself
+            let supervised = AssertUnwindSafe(supervisor.supervise(daemon, factory, kill_rx));
+            if supervised.catch_unwind().await.is_err() {
+                supervisor.set_status(DaemonStatus::Panicked);
             }
             let _ = finish_tx.send(());
         });
         self.set_component(&handler_name, handle.clone());
+        if !self.is_root() {
+            let mut child_daemons = self.child_daemons.lock().unwrap();
+            child_daemons.retain(|handle| !handle.state().is_terminated());
+            child_daemons.push(handle.clone());
+        }
         Ok(handle)
     }
     pub fn get_daemon_handle<D>(&self) -> Option<DaemonHandle>
@@ -171,4 +319,50 @@ impl Moonbase {
         let handler_name = ComponentName::new_daemon_handle::<D, Self>();
         self.get_component(&handler_name)
     }
+    /// Handles of every daemon ever run in this moonbase, including terminated ones.
+    pub fn daemon_handles(&self) -> Vec<DaemonHandle> {
+        let components = self.components.read().unwrap();
+        components.iter::<DaemonHandle>().collect()
+    }
+    /// Snapshots of every daemon ever run in this moonbase, sorted by name.
+    pub fn daemons(&self) -> Vec<DaemonInfo> {
+        let mut daemons = self
+            .daemon_handles()
+            .iter()
+            .map(DaemonHandle::info)
+            .collect::<Vec<_>>();
+        daemons.sort_by(|a, b| a.name.cmp(&b.name));
+        daemons
+    }
+    /// Kill every daemon and wait until they stop or the timeout elapses, on the root the
+    /// daemons run in its child contexts are killed as well.
+    ///
+    /// Returns the handles of daemons which failed to stop in time.
+    pub async fn kill_all_daemons(&self, timeout: Duration) -> Vec<DaemonHandle> {
+        let mut handles = self.daemon_handles();
+        if self.is_root() {
+            handles.append(&mut self.child_daemons.lock().unwrap());
+        }
+        if handles.is_empty() {
+            return handles;
+        }
+        for handle in &handles {
+            handle.kill_guard();
+        }
+        let all_stopped = join_all(handles.iter().map(DaemonHandle::wait));
+        match self.runtime() {
+            Ok(runtime) => futures::select! {
+                _ = all_stopped.fuse() => {}
+                _ = runtime.sleep(timeout).fuse() => {}
+            },
+            // daemons can't be spawned without a runtime, nothing to time out
+            Err(_) => {
+                all_stopped.await;
+            }
+        }
+        handles
+            .into_iter()
+            .filter(|handle| !handle.state().is_terminated())
+            .collect()
+    }
 }
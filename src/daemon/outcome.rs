@@ -0,0 +1,103 @@
+use std::{any::Any, sync::Arc, time::SystemTime};
+
+/// How a run of a daemon ended.
+///
+/// **A daemon returning itself runs again.** It's converted into [`DaemonOutcome::Continue`],
+/// which restarts it like a failure does, counted by its restart policy, so daemons written as
+/// a loop of runs keep working. Return [`DaemonOutcome::Stop`] to finish for good. A `Result`
+/// of itself runs again on `Ok`, and treats the error as retryable.
+#[derive(Debug)]
+pub enum DaemonOutcome<D> {
+    /// run the daemon again, following its restart policy, what returning the daemon means
+    Continue(D),
+    /// the daemon finished its work, don't restart it
+    Stop,
+    /// the run failed, restart a daemon extracted from context following its restart policy
+    Failed(anyhow::Error),
+    /// the run failed and can't be recovered, don't restart it
+    Fatal(anyhow::Error),
+}
+
+impl<D> DaemonOutcome<D> {
+    pub fn failed(error: impl Into<anyhow::Error>) -> Self {
+        DaemonOutcome::Failed(error.into())
+    }
+    pub fn fatal(error: impl Into<anyhow::Error>) -> Self {
+        DaemonOutcome::Fatal(error.into())
+    }
+}
+
+impl<D> From<D> for DaemonOutcome<D> {
+    fn from(daemon: D) -> Self {
+        DaemonOutcome::Continue(daemon)
+    }
+}
+
+impl<D, E> From<Result<D, E>> for DaemonOutcome<D>
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(result: Result<D, E>) -> Self {
+        match result {
+            Ok(daemon) => DaemonOutcome::Continue(daemon),
+            Err(error) => DaemonOutcome::Failed(error.into()),
+        }
+    }
+}
+
+/// The last error a daemon met, see [`DaemonHandle::last_error`](super::DaemonHandle::last_error).
+#[derive(Debug, Clone)]
+pub struct DaemonError {
+    pub error: Arc<anyhow::Error>,
+    /// whether the error stopped the daemon for good
+    pub fatal: bool,
+    pub occurred_at: SystemTime,
+}
+
+impl DaemonError {
+    pub(crate) fn new(error: anyhow::Error, fatal: bool) -> Self {
+        Self {
+            error: Arc::new(error),
+            fatal,
+            occurred_at: SystemTime::now(),
+        }
+    }
+    /// The panic message if the error is caused by a panic.
+    pub fn panic_message(&self) -> Option<&str> {
+        self.error
+            .downcast_ref::<DaemonPanic>()
+            .map(|panic| panic.message.as_str())
+    }
+}
+
+/// A panic caught from a daemon run.
+#[derive(Debug, Clone)]
+pub struct DaemonPanic {
+    pub message: String,
+}
+
+impl DaemonPanic {
+    pub(crate) fn from_payload(payload: Box<dyn Any + Send>) -> Self {
+        Self {
+            message: crate::utils::panic_message(payload),
+        }
+    }
+}
+
+impl std::fmt::Display for DaemonPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "daemon panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for DaemonPanic {}
+
+impl std::fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.fatal {
+            write!(f, "fatal: {:#}", self.error)
+        } else {
+            write!(f, "{:#}", self.error)
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::Context,
+};
+
+use futures::task::AtomicWaker;
+
+use super::DaemonHandle;
+
+/// Shared between a daemon handle and its supervisor, see [`DaemonHandle::pause`].
+#[derive(Debug, Default)]
+pub(crate) struct PauseControl {
+    paused: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl PauseControl {
+    fn set(&self, paused: bool) -> bool {
+        let changed = self.paused.swap(paused, Ordering::SeqCst) != paused;
+        if changed {
+            self.waker.wake();
+        }
+        changed
+    }
+    /// Whether the run should stay pending, the run is woken up when it's resumed.
+    pub(crate) fn poll_paused(&self, cx: &mut Context<'_>) -> bool {
+        if !self.paused.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.waker.register(cx.waker());
+        // resumed before the waker is registered
+        self.paused.load(Ordering::SeqCst)
+    }
+}
+
+impl DaemonHandle {
+    /// Stop polling the current run until [`resume`](DaemonHandle::resume)d.
+    ///
+    /// The run is kept as is, and so are the restart counters. It takes effect when the
+    /// supervisor polls the run next time, and applies to later runs until resumed.
+    /// Returns false if it's already paused.
+    pub fn pause(&self) -> bool {
+        self.pause.set(true)
+    }
+    /// Continue polling the run, returns false if it's not paused.
+    pub fn resume(&self) -> bool {
+        self.pause.set(false)
+    }
+    pub fn is_paused(&self) -> bool {
+        self.pause.paused.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How long to wait before restarting a daemon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// wait the same time before every restart
+    Fixed(Duration),
+    /// wait `initial * factor ^ attempt`, capped at `max`
+    ///
+    /// every delay is randomly scaled within `1 ± jitter`, so that daemons crashed together
+    /// won't restart together, and it's still capped at `max` after.
+    Exponential {
+        initial: Duration,
+        max: Duration,
+        factor: f64,
+        jitter: f64,
+    },
+}
+
+impl Backoff {
+    /// Exponential backoff doubling from `initial` up to `max`, without jitter.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Backoff::Exponential {
+            initial,
+            max,
+            factor: 2.0,
+            jitter: 0.0,
+        }
+    }
+    /// Set the jitter ratio of an exponential backoff, it's clamped into `0.0..=1.0`.
+    pub fn with_jitter(self, jitter: f64) -> Self {
+        match self {
+            Backoff::Exponential {
+                initial,
+                max,
+                factor,
+                ..
+            } => Backoff::Exponential {
+                initial,
+                max,
+                factor,
+                jitter: jitter.clamp(0.0, 1.0),
+            },
+            fixed => fixed,
+        }
+    }
+    /// The delay before the `attempt`-th consecutive restart, starting from 0.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                max,
+                factor,
+                jitter,
+            } => {
+                let exponent = attempt.min(i32::MAX as u32) as i32;
+                let exponential = initial.as_secs_f64() * factor.powi(exponent);
+                // `min` ignores a NaN from a NaN factor
+                let capped = exponential.min(max.as_secs_f64());
+                let scale = 1.0 + jitter * (fastrand::f64() * 2.0 - 1.0);
+                // too long to be a duration, or NaN from a NaN jitter
+                let delay = Duration::try_from_secs_f64(capped * scale).unwrap_or(max);
+                delay.min(max)
+            }
+        }
+    }
+    /// A run lasting longer than this is considered healthy, and resets the attempts.
+    fn reset_after(&self) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { max, .. } => max,
+        }
+    }
+}
+
+/// Erlang-style restart intensity, a daemon gives up if it needs more than `max_restarts`
+/// restarts within `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartIntensity {
+    pub max_restarts: usize,
+    pub period: Duration,
+}
+
+/// How a daemon is restarted after its run ends.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RestartPolicy {
+    /// total restarts allowed, `None` for unlimited
+    pub max_restarts: Option<usize>,
+    /// delay before each restart, `None` to restart immediately
+    pub backoff: Option<Backoff>,
+    /// restarts allowed within a sliding time window
+    pub intensity: Option<RestartIntensity>,
+}
+
+impl RestartPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Never restart the daemon.
+    pub fn never() -> Self {
+        Self::new().max_restarts(0)
+    }
+    pub fn max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = Some(max_restarts);
+        self
+    }
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+    pub fn intensity(mut self, max_restarts: usize, period: Duration) -> Self {
+        self.intensity = Some(RestartIntensity {
+            max_restarts,
+            period,
+        });
+        self
+    }
+    pub(crate) fn tracker(self) -> RestartTracker {
+        RestartTracker {
+            policy: self,
+            restarts: 0,
+            attempt: 0,
+            history: VecDeque::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RestartTracker {
+    policy: RestartPolicy,
+    restarts: usize,
+    attempt: u32,
+    history: VecDeque<Instant>,
+}
+
+impl RestartTracker {
+    /// Decide what to do after a run which lasted `run_time` ended at `now`.
+    ///
+    /// Returns the delay before the next restart, or `None` if the daemon should give up.
+    pub(crate) fn next_restart(&mut self, run_time: Duration, now: Instant) -> Option<Duration> {
+        if let Some(max_restarts) = self.policy.max_restarts {
+            if self.restarts >= max_restarts {
+                return None;
+            }
+        }
+        if let Some(intensity) = self.policy.intensity {
+            while let Some(earliest) = self.history.front() {
+                if now.duration_since(*earliest) > intensity.period {
+                    self.history.pop_front();
+                } else {
+                    break;
+                }
+            }
+            if self.history.len() >= intensity.max_restarts {
+                return None;
+            }
+            self.history.push_back(now);
+        }
+        self.restarts += 1;
+        let Some(backoff) = self.policy.backoff else {
+            return Some(Duration::ZERO);
+        };
+        if run_time > backoff.reset_after() {
+            self.attempt = 0;
+        }
+        let delay = backoff.delay(self.attempt);
+        self.attempt = self.attempt.saturating_add(1);
+        Some(delay)
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    future::{poll_fn, Future},
+    panic::AssertUnwindSafe,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use crossbeam::atomic::AtomicCell;
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+
+use crate::{runtime::Runtime, Moonbase};
+
+use super::{
+    event::{DaemonEventBus, DaemonEventKind},
+    group::{GroupEventKind, GroupLink},
+    pause::PauseControl,
+    Daemon, DaemonError, DaemonFactory, DaemonOutcome, DaemonPanic, DaemonStatus, RestartTracker,
+};
+
+pub(crate) struct Supervisor<R> {
+    pub(crate) name: String,
+    pub(crate) events: DaemonEventBus,
+    pub(crate) context: Moonbase,
+    pub(crate) runtime: R,
+    pub(crate) tracker: RestartTracker,
+    pub(crate) status: Arc<AtomicCell<DaemonStatus>>,
+    pub(crate) restarted: Arc<AtomicUsize>,
+    pub(crate) last_error: Arc<Mutex<Option<DaemonError>>>,
+    pub(crate) running_since: Arc<AtomicCell<Option<Instant>>>,
+    pub(crate) pause: Arc<PauseControl>,
+    pub(crate) link: Option<GroupLink>,
+}
+
+impl<R: Runtime> Supervisor<R> {
+    /// Run the daemon until it stops, is killed or gives up restarting.
+    pub(crate) async fn supervise<D>(
+        &mut self,
+        mut daemon: D,
+        factory: DaemonFactory<D>,
+        mut kill: oneshot::Receiver<()>,
+    ) where
+        D: Daemon<Moonbase>,
+        D::IntoFuture: Send + 'static,
+    {
+        self.set_status(DaemonStatus::Starting);
+        let status = loop {
+            let started_at = self.runtime.now();
+            self.running_since.store(Some(started_at));
+            self.set_status(DaemonStatus::Running);
+            let affected: BoxFuture<'_, _> = match &self.link {
+                Some(link) => link.affected().boxed(),
+                None => futures::future::pending().boxed(),
+            };
+            // the daemon to run next, `None` to extract a new one from context
+            let mut run = pin!(AssertUnwindSafe(daemon.into_future()).catch_unwind());
+            let mut paused = false;
+            // the run is not polled while paused
+            let run = poll_fn(|cx| {
+                if self.pause.poll_paused(cx) {
+                    if !paused {
+                        paused = true;
+                        self.running_since.store(None);
+                        self.set_status(DaemonStatus::Paused);
+                    }
+                    return Poll::Pending;
+                }
+                if paused {
+                    paused = false;
+                    self.running_since.store(Some(self.runtime.now()));
+                    self.set_status(DaemonStatus::Running);
+                }
+                run.as_mut().poll(cx)
+            });
+            let next_daemon = futures::select! {
+                result = run.fuse() => {
+                    // the status to end with if giving up now
+                    let (next_daemon, given_up) = match result {
+                        Ok(outcome) => match outcome.into() {
+                            DaemonOutcome::Continue(next_daemon) => {
+                                (Some(next_daemon), DaemonStatus::Terminated)
+                            }
+                            DaemonOutcome::Stop => break DaemonStatus::Terminated,
+                            DaemonOutcome::Failed(error) => {
+                                self.record(error, false);
+                                (None, DaemonStatus::Failed)
+                            }
+                            DaemonOutcome::Fatal(error) => {
+                                self.record(error, true);
+                                self.notify(GroupEventKind::Terminate);
+                                break DaemonStatus::Failed;
+                            }
+                        },
+                        Err(payload) => {
+                            self.record(DaemonPanic::from_payload(payload).into(), false);
+                            (None, DaemonStatus::Panicked)
+                        }
+                    };
+                    self.set_status(DaemonStatus::Starting);
+                    let now = self.runtime.now();
+                    let Some(delay) = self.tracker.next_restart(now - started_at, now) else {
+                        self.notify(GroupEventKind::Terminate);
+                        break given_up;
+                    };
+                    let event = self.notify(GroupEventKind::Restart);
+                    let killed = !cool_down(&self.runtime, delay, &mut kill).await;
+                    // release the members waiting for this one, even if killed
+                    if let (Some(link), Some(event)) = (&self.link, event) {
+                        link.restarted(event);
+                    }
+                    if killed {
+                        break DaemonStatus::Terminated;
+                    }
+                    if !delay.is_zero() {
+                        self.publish(DaemonEventKind::CooledDown(delay));
+                    }
+                    next_daemon
+                }
+                event = affected.fuse() => {
+                    self.set_status(DaemonStatus::Starting);
+                    if event.kind == GroupEventKind::Terminate {
+                        break DaemonStatus::Terminated;
+                    }
+                    let link = self.link.as_ref().expect("group event without group");
+                    futures::select! {
+                        _ = link.wait_restarted(event).fuse() => {}
+                        _ = kill => break DaemonStatus::Terminated,
+                    }
+                    None
+                }
+                _ = kill => break DaemonStatus::Terminated,
+            };
+            daemon = match next_daemon {
+                Some(next_daemon) => next_daemon,
+                None => match factory(self.context.clone()).await {
+                    Ok(next_daemon) => next_daemon,
+                    Err(error) => {
+                        self.record(error, true);
+                        self.notify(GroupEventKind::Terminate);
+                        break DaemonStatus::Failed;
+                    }
+                },
+            };
+            let restarted = self.restarted.fetch_add(1, Ordering::SeqCst) + 1;
+            self.publish(DaemonEventKind::Restarted(restarted));
+        };
+        self.set_status(status);
+    }
+    pub(crate) fn set_status(&self, status: DaemonStatus) {
+        self.status.store(status);
+        let kind = match status {
+            DaemonStatus::Starting => DaemonEventKind::Starting,
+            DaemonStatus::Running => DaemonEventKind::Running,
+            DaemonStatus::Paused => DaemonEventKind::Paused,
+            terminated => DaemonEventKind::Terminated(terminated),
+        };
+        self.publish(kind);
+    }
+    fn publish(&self, kind: DaemonEventKind) {
+        self.events.publish(&self.name, kind);
+    }
+    fn record(&self, error: anyhow::Error, fatal: bool) {
+        let error = DaemonError::new(error, fatal);
+        *self.last_error.lock().unwrap() = Some(error.clone());
+        self.publish(DaemonEventKind::Failed(error));
+    }
+    fn notify(&self, kind: GroupEventKind) -> Option<super::group::GroupEvent> {
+        self.link.as_ref().map(|link| link.exited(kind))
+    }
+}
+
+/// Sleep before a restart, returns false if the daemon is killed meanwhile.
+async fn cool_down<R: Runtime>(
+    runtime: &R,
+    delay: Duration,
+    kill: &mut oneshot::Receiver<()>,
+) -> bool {
+    if delay.is_zero() {
+        return true;
+    }
+    futures::select! {
+        _ = runtime.sleep(delay).fuse() => true,
+        _ = kill.fuse() => false,
+    }
+}
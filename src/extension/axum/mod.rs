@@ -1,5 +1,9 @@
+mod request;
+
+pub use request::*;
+
 use crate::components::{ComponentName, MoonbaseComponent};
-use crate::resource::{MoonbaseResource, Resource};
+use crate::resource::{MoonbaseResource, Resource, ResourceError, SharedResource};
 use crate::Moonbase;
 
 impl MoonbaseComponent for axum::Router<Moonbase> {}
@@ -41,21 +45,45 @@ impl axum::extract::FromRequestParts<Moonbase> for Moonbase {
     }
 }
 
+/// A resource is missing or fails to initialize, rendered as a 500 with the error as body.
+#[derive(Debug, Clone)]
+pub struct ResourceRejection(pub ResourceError);
+
+impl axum::response::IntoResponse for ResourceRejection {
+    fn into_response(self) -> axum::response::Response {
+        let status = axum::http::StatusCode::INTERNAL_SERVER_ERROR;
+        (status, self.0.to_string()).into_response()
+    }
+}
+
 #[async_trait::async_trait]
 impl<R> axum::extract::FromRequestParts<Moonbase> for Resource<R>
 where
     R: MoonbaseResource,
 {
-    type Rejection = String;
+    type Rejection = ResourceRejection;
+
+    async fn from_request_parts(
+        _parts: &mut axum::http::request::Parts,
+        state: &Moonbase,
+    ) -> Result<Self, Self::Rejection> {
+        let resource = state.get_or_init_resource::<R>().await;
+        resource.map(Resource).map_err(ResourceRejection)
+    }
+}
+
+#[async_trait::async_trait]
+impl<R> axum::extract::FromRequestParts<Moonbase> for SharedResource<R>
+where
+    R: std::any::Any + Send + Sync,
+{
+    type Rejection = ResourceRejection;
 
     async fn from_request_parts(
         _parts: &mut axum::http::request::Parts,
         state: &Moonbase,
     ) -> Result<Self, Self::Rejection> {
-        let resource = state.get_resource::<R>();
-        match resource {
-            Some(resource) => Ok(Resource(resource)),
-            None => Err(format!("Resource {} not found", std::any::type_name::<R>())),
-        }
+        let resource = state.get_or_init_resource_arc::<R>().await;
+        resource.map(SharedResource).map_err(ResourceRejection)
     }
 }
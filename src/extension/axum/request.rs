@@ -0,0 +1,375 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{request::Parts, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+
+use crate::{
+    context::Context,
+    extract::{ExtractFrom, TryExtractFrom},
+    module::Dependency,
+    resource::{MoonbaseResource, Resource, ResourceError, SharedResource},
+    Moonbase,
+};
+
+/// Header to read the request id from, and to echo it back in the response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Identifies a request, taken from [`REQUEST_ID_HEADER`] or generated.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(Arc<str>);
+
+impl RequestId {
+    pub fn new(id: impl Into<Arc<str>>) -> Self {
+        Self(id.into())
+    }
+    /// A process-wide unique id.
+    pub fn generate() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self::new(format!("{:016x}", NEXT_ID.fetch_add(1, Ordering::Relaxed)))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Who sent the request, as resolved by the [`Authenticator`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Principal {
+    pub id: String,
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            roles: Vec::new(),
+        }
+    }
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.roles.push(role.into());
+        self
+    }
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+type AuthenticateFn =
+    dyn Fn(Moonbase, HeaderMap) -> BoxFuture<'static, Option<Principal>> + Send + Sync;
+
+/// Resolves the [`Principal`] of a request from its headers, set it as a resource to enable
+/// authentication in [`request_context`].
+#[derive(Clone)]
+pub struct Authenticator {
+    authenticate: Arc<AuthenticateFn>,
+}
+
+impl std::fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Authenticator").finish_non_exhaustive()
+    }
+}
+
+impl Authenticator {
+    pub fn new<F, Fut>(authenticate: F) -> Self
+    where
+        F: Fn(Moonbase, HeaderMap) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<Principal>> + Send + 'static,
+    {
+        Self {
+            authenticate: Arc::new(move |context, headers| {
+                Box::pin(authenticate(context, headers))
+            }),
+        }
+    }
+    pub async fn authenticate(&self, context: &Moonbase, headers: &HeaderMap) -> Option<Principal> {
+        (self.authenticate)(context.clone(), headers.clone()).await
+    }
+}
+
+/// Context of a single request, built by the [`request_context`] middleware.
+///
+/// It carries a [child](Moonbase::child) of the app moonbase, with the [`RequestId`] and the
+/// [`Principal`] set as resources, so anything extracted from it is scoped to the request.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    request_id: RequestId,
+    method: Method,
+    uri: Uri,
+    headers: Arc<HeaderMap>,
+    principal: Option<Principal>,
+    moonbase: Moonbase,
+}
+
+impl RequestContext {
+    pub async fn new(app: &Moonbase, parts: &Parts) -> Self {
+        let request_id = parts
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .map(RequestId::new)
+            .unwrap_or_else(RequestId::generate);
+        let principal = match app.get_resource::<Authenticator>() {
+            Some(authenticator) => authenticator.authenticate(app, &parts.headers).await,
+            None => None,
+        };
+        let moonbase = app.child();
+        moonbase.set_resource(request_id.clone());
+        if let Some(principal) = &principal {
+            moonbase.set_resource(principal.clone());
+        }
+        Self {
+            request_id,
+            method: parts.method.clone(),
+            uri: parts.uri.clone(),
+            headers: Arc::new(parts.headers.clone()),
+            principal,
+            moonbase,
+        }
+    }
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
+    }
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+    pub fn principal(&self) -> Option<&Principal> {
+        self.principal.as_ref()
+    }
+    /// The request scoped child moonbase.
+    pub fn moonbase(&self) -> &Moonbase {
+        &self.moonbase
+    }
+}
+
+impl Context for RequestContext {}
+
+impl AsRef<Moonbase> for RequestContext {
+    fn as_ref(&self) -> &Moonbase {
+        &self.moonbase
+    }
+}
+
+/// Build a [`RequestContext`] for each request, and echo its id in the response.
+///
+/// ```ignore
+/// router.layer(axum::middleware::from_fn_with_state(moonbase.clone(), request_context))
+/// ```
+pub async fn request_context(
+    State(app): State<Moonbase>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let context = RequestContext::new(&app, &parts).await;
+    let request_id = HeaderValue::from_str(context.request_id.as_str());
+    parts.extensions.insert(context);
+    let mut response = next.run(Request::from_parts(parts, body)).await;
+    if let Ok(request_id) = request_id {
+        response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    }
+    response
+}
+
+impl ExtractFrom<RequestContext> for RequestContext {
+    async fn extract_from(context: &RequestContext) -> Self {
+        context.clone()
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl ExtractFrom<RequestContext> for Moonbase {
+    async fn extract_from(context: &RequestContext) -> Self {
+        context.moonbase.clone()
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl ExtractFrom<RequestContext> for RequestId {
+    async fn extract_from(context: &RequestContext) -> Self {
+        context.request_id.clone()
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl ExtractFrom<RequestContext> for Option<Principal> {
+    async fn extract_from(context: &RequestContext) -> Self {
+        context.principal.clone()
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl<T> TryExtractFrom<RequestContext> for Resource<T>
+where
+    T: MoonbaseResource,
+{
+    type Error = ResourceError;
+    async fn try_extract_from(context: &RequestContext) -> Result<Self, Self::Error> {
+        let resource = context.moonbase.get_or_init_resource::<T>().await;
+        resource.map(Resource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<T>()]
+    }
+}
+
+impl<T> TryExtractFrom<RequestContext> for SharedResource<T>
+where
+    T: std::any::Any + Send + Sync,
+{
+    type Error = ResourceError;
+    async fn try_extract_from(context: &RequestContext) -> Result<Self, Self::Error> {
+        let resource = context.moonbase.get_or_init_resource_arc::<T>().await;
+        resource.map(SharedResource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<T>()]
+    }
+}
+
+impl<T> ExtractFrom<RequestContext> for Option<Resource<T>>
+where
+    T: MoonbaseResource,
+{
+    async fn extract_from(context: &RequestContext) -> Self {
+        let resource = context.moonbase.get_or_init_resource::<T>().await;
+        resource.ok().map(Resource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl<T> ExtractFrom<RequestContext> for Option<SharedResource<T>>
+where
+    T: std::any::Any + Send + Sync,
+{
+    async fn extract_from(context: &RequestContext) -> Self {
+        let resource = context.moonbase.get_or_init_resource_arc::<T>().await;
+        resource.ok().map(SharedResource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+/// The [`request_context`] middleware is not applied.
+#[derive(Debug, Clone, Copy)]
+pub struct MissingRequestContext;
+
+impl IntoResponse for MissingRequestContext {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "request context not found",
+        )
+            .into_response()
+    }
+}
+
+/// The request has no authenticated [`Principal`].
+#[derive(Debug, Clone, Copy)]
+pub struct Unauthenticated;
+
+impl IntoResponse for Unauthenticated {
+    fn into_response(self) -> Response {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> axum::extract::FromRequestParts<S> for RequestContext
+where
+    S: Send + Sync,
+{
+    type Rejection = MissingRequestContext;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<RequestContext>()
+            .cloned()
+            .ok_or(MissingRequestContext)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> axum::extract::FromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let context = RequestContext::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        context
+            .principal
+            .ok_or_else(|| Unauthenticated.into_response())
+    }
+}
+
+/// Extract `T` from the [`RequestContext`] of the request.
+///
+/// If it fails the request is rejected as a 500 with the error as body, like a
+/// [`ResourceRejection`](super::ResourceRejection).
+///
+/// ```ignore
+/// async fn handler(Scoped(service): Scoped<UserService>) { /* ... */ }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scoped<T>(pub T);
+
+#[async_trait::async_trait]
+impl<S, T> axum::extract::FromRequestParts<S> for Scoped<T>
+where
+    S: Send + Sync,
+    T: TryExtractFrom<RequestContext>,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let context = RequestContext::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        match context.try_extract::<T>().await {
+            Ok(value) => Ok(Scoped(value)),
+            Err(error) => {
+                Err((StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response())
+            }
+        }
+    }
+}
@@ -18,13 +18,29 @@ where
         let moonbase = req
             .app_state::<crate::Moonbase>()
             .with_context(|| "Moonbase not found")?;
-        let resource = moonbase
-            .get_resource::<R>()
-            .with_context(|| format!("Resource {} not found", std::any::type_name::<R>()))?;
+        let resource = moonbase.get_or_init_resource::<R>().await?;
         Ok(crate::resource::Resource(resource))
     }
 }
 
+impl<E, R> ntex::web::FromRequest<E> for crate::resource::SharedResource<R>
+where
+    R: std::any::Any + Send + Sync,
+{
+    type Error = anyhow::Error;
+
+    async fn from_request(
+        req: &web::HttpRequest,
+        _payload: &mut ntex::http::Payload,
+    ) -> Result<Self, Self::Error> {
+        let moonbase = req
+            .app_state::<crate::Moonbase>()
+            .with_context(|| "Moonbase not found")?;
+        let resource = moonbase.get_or_init_resource_arc::<R>().await?;
+        Ok(crate::resource::SharedResource(resource))
+    }
+}
+
 impl<E> ntex::web::FromRequest<E> for Moonbase
 {
     type Error = anyhow::Error;
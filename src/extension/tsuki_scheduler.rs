@@ -1,13 +1,9 @@
-use std::{convert::Infallible, future::IntoFuture, pin::Pin};
+use std::{future::IntoFuture, pin::Pin};
 
 use futures::Future;
 use tsuki_scheduler::prelude::*;
 
-use crate::{
-    daemon::Daemon,
-    extract::{ExtractFrom, TryExtractFrom},
-    Moonbase,
-};
+use crate::{daemon::Daemon, extract::ExtractFrom, module::Dependency, Moonbase};
 
 pub use tsuki_scheduler;
 
@@ -25,13 +21,8 @@ impl ExtractFrom<Moonbase> for TsukiScheduler {
 
         TsukiScheduler { runner }
     }
-}
-
-impl TryExtractFrom<Moonbase> for TsukiScheduler {
-    type Error = Infallible;
-
-    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
-        Ok(TsukiScheduler::extract_from(context).await)
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
     }
 }
 
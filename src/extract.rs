@@ -1,12 +1,21 @@
-use crate::context::Context;
-use std::future::Future;
+use crate::{context::Context, module::Dependency};
+use std::{any::Any, convert::Infallible, future::Future};
+
+#[cfg(feature = "macros")]
+pub use moonbase_macros::Extract;
 pub trait ExtractFrom<C>: Sized
 where
     C: Context,
 {
     fn extract_from(context: &C) -> impl Future<Output = Self> + Send;
+    /// Resources the extraction requires, checked by [`Moonbase::validate`](crate::Moonbase::validate).
+    ///
+    /// It has no default, so a hand-written extractor can't be validated as requiring nothing
+    /// by mistake.
+    fn requirements() -> Vec<Dependency>;
 }
 
+/// The error is handled by the extractor, so it requires nothing, like an `Option`.
 impl<C, T> ExtractFrom<C> for Result<T, T::Error>
 where
     T: TryExtractFrom<C>,
@@ -15,6 +24,9 @@ where
     async fn extract_from(context: &C) -> Self {
         T::try_extract_from(context).await
     }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
 }
 
 pub trait TryExtractFrom<C>: Sized
@@ -23,13 +35,56 @@ where
 {
     type Error: std::fmt::Debug + std::fmt::Display + Send + Sync + 'static;
     fn try_extract_from(context: &C) -> impl Future<Output = Result<Self, Self::Error>> + Send;
+    /// Resources the extraction requires, checked by [`Moonbase::validate`](crate::Moonbase::validate).
+    ///
+    /// A hand-written impl, e.g. of a daemon, lists what `try_extract_from` fails without.
+    fn requirements() -> Vec<Dependency>;
 }
 
+/// Every infallible extractor is a fallible one never failing, so it's accepted wherever a
+/// [`TryExtractFrom`] is required, e.g. by [`Moonbase::run_daemon`](crate::Moonbase::run_daemon).
+impl<C, T> TryExtractFrom<C> for T
+where
+    T: ExtractFrom<C>,
+    C: Context,
+{
+    type Error = Infallible;
+    async fn try_extract_from(context: &C) -> Result<Self, Infallible> {
+        Ok(T::extract_from(context).await)
+    }
+    fn requirements() -> Vec<Dependency> {
+        <T as ExtractFrom<C>>::requirements()
+    }
+}
 
-macro_rules! fora {
-    () => {
-        
-    };
+/// Report an extraction error with context, it can still be downcast to its type.
+pub(crate) fn into_anyhow<E>(error: E) -> anyhow::Error
+where
+    E: std::fmt::Debug + std::fmt::Display + Send + Sync + 'static,
+{
+    // keep the context chain of an error already reported
+    let mut error = Some(error);
+    if let Some(error) = (&mut error as &mut dyn Any).downcast_mut::<Option<anyhow::Error>>() {
+        return error.take().expect("taken once");
+    }
+    anyhow::Error::msg(error.expect("taken once"))
+}
+
+/// Tuples of fallible extractors, failing on the first element which fails.
+///
+/// A tuple of infallible extractors is an [`ExtractFrom`] itself, while a tuple mixing fallible
+/// ones is extracted by this trait, see [`ContextExt::try_call`](crate::context::ContextExt::try_call)
+/// and [`ContextExt::try_extract_tuple`](crate::context::ContextExt::try_extract_tuple). A field
+/// of `#[derive(Extract)]` or a parameter of `#[daemon]` and `#[module]` may be such a tuple.
+///
+/// Such a tuple is no longer a [`TryExtractFrom`], which would overlap the impl for every
+/// [`ExtractFrom`], so it can't be passed to [`Context::try_extract`] or be a daemon.
+pub trait TryExtractTuple<C>: Sized
+where
+    C: Context,
+{
+    fn try_extract_tuple(context: &C) -> impl Future<Output = Result<Self, anyhow::Error>> + Send;
+    fn requirements() -> Vec<Dependency>;
 }
 
 macro_rules! extract_tuples {
@@ -43,19 +98,30 @@ macro_rules! extract_tuples {
             async fn extract_from(_context: &C) -> Self {
                ( $($T::extract_from(_context).await, )*)
             }
+            fn requirements() -> Vec<Dependency> {
+                std::iter::empty()
+                    $(.chain(<$T as ExtractFrom<C>>::requirements()))*
+                    .collect()
+            }
         }
-        impl<C, $($T,)* > TryExtractFrom<C> for ($($T,)*)
+        impl<C, $($T,)*> TryExtractTuple<C> for ($($T,)*)
         where
-            $(
-                $T: TryExtractFrom<C> + Send,
-                $T::Error: std::error::Error + Send + Sync + 'static,
-            )*
+            $($T: TryExtractFrom<C> + Send,)*
             C: Context,
         {
-            type Error = anyhow::Error;
             #[allow(clippy::unused_unit, )]
-            async fn try_extract_from(_context: &C) -> Result<Self, anyhow::Error> {
-               Ok(( $($T::try_extract_from(_context).await?, )*))
+            async fn try_extract_tuple(_context: &C) -> Result<Self, anyhow::Error> {
+               Ok(( $(
+                   anyhow::Context::with_context(
+                       $T::try_extract_from(_context).await.map_err(into_anyhow),
+                       || format!("fail to extract `{}`", std::any::type_name::<$T>()),
+                   )?,
+               )*))
+            }
+            fn requirements() -> Vec<Dependency> {
+                std::iter::empty()
+                    $(.chain(<$T as TryExtractFrom<C>>::requirements()))*
+                    .collect()
             }
         }
     };
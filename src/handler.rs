@@ -16,6 +16,7 @@ pub trait Adapter {
     type Args;
 }
 
+#[allow(clippy::type_complexity)]
 pub struct Fallible<A, T, E> {
     marker: PhantomData<(A, fn() -> (T, E))>,
 }
@@ -1,12 +1,22 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use components::ComponentRepository;
 use context::Context;
 use crossbeam::sync::ShardedLock;
+use daemon::{ChildDaemons, DaemonEventBus};
 use extract::ExtractFrom;
+use module::ModuleRegistry;
 use resource::ResourceRepository;
 use signal::{Signal, SignalKey};
+use task::TaskRegistry;
 
+pub mod builder;
 pub mod components;
 pub mod context;
 pub mod daemon;
@@ -16,27 +26,43 @@ pub mod handler;
 pub mod module;
 pub mod resource;
 pub mod runtime;
+pub mod shutdown;
 pub mod signal;
+pub mod task;
 pub mod utils;
+pub mod validate;
+
+#[doc(hidden)]
+#[cfg(feature = "macros")]
+pub mod __private;
+
+#[cfg(feature = "macros")]
+pub use moonbase_macros::{daemon, module};
 
 pub mod prelude {
     pub use crate::{
-        components::*, context::*, daemon::*, extract::*, module::*, resource::*, signal::*,
-        AppContext, Moonbase,
+        builder::*, components::*, context::*, daemon::*, extract::*, module::*, resource::*,
+        shutdown::*, signal::*, task::*, validate::*, AppContext, Moonbase,
     };
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Moonbase {
     id: u64,
+    parent: Option<Arc<Moonbase>>,
     resources: ResourceRepository,
     components: ComponentRepository,
     signals: Arc<ShardedLock<HashMap<SignalKey, Signal>>>,
+    modules: ModuleRegistry,
+    daemon_events: DaemonEventBus,
+    child_daemons: ChildDaemons,
+    tasks: TaskRegistry,
 }
 
 pub type AppContext = Moonbase;
 
 impl Moonbase {
+    /// `0` for a root moonbase, unique for each child.
     pub fn id(&self) -> u64 {
         self.id
     }
@@ -44,10 +70,53 @@ impl Moonbase {
     pub fn new() -> Self {
         Moonbase {
             id: 0,
+            parent: None,
+            resources: ResourceRepository::default(),
+            components: ComponentRepository::default(),
+            signals: Arc::new(ShardedLock::new(Default::default())),
+            modules: ModuleRegistry::default(),
+            daemon_events: DaemonEventBus::default(),
+            child_daemons: ChildDaemons::default(),
+            tasks: TaskRegistry::default(),
+        }
+    }
+}
+
+impl Moonbase {
+    /// Derive a child context, e.g. per request, per tenant or per test.
+    ///
+    /// Resources, components and signals not found in the child are read from its parent,
+    /// while setting or removing them only affects the child. Modules, daemon events and
+    /// tasks are shared with the parent, and daemons run in the child are stopped along with
+    /// the root's. [`shutdown`](Moonbase::shutdown) on a child only stops its own daemons and
+    /// tasks.
+    pub fn child(&self) -> Moonbase {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Moonbase {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            parent: Some(Arc::new(self.clone())),
             resources: ResourceRepository::default(),
             components: ComponentRepository::default(),
             signals: Arc::new(ShardedLock::new(Default::default())),
+            modules: self.modules.clone(),
+            daemon_events: self.daemon_events.clone(),
+            child_daemons: self.child_daemons.clone(),
+            tasks: self.tasks.clone(),
+        }
+    }
+    pub fn parent(&self) -> Option<&Moonbase> {
+        self.parent.as_deref()
+    }
+    pub fn is_root(&self) -> bool {
+        self.parent.is_none()
+    }
+    /// The moonbase this context is derived from at the top.
+    pub fn root(&self) -> &Moonbase {
+        let mut context = self;
+        while let Some(parent) = context.parent() {
+            context = parent;
         }
+        context
     }
 }
 
@@ -55,7 +124,21 @@ impl ExtractFrom<Moonbase> for Moonbase {
     async fn extract_from(context: &Moonbase) -> Self {
         context.clone()
     }
+    fn requirements() -> Vec<module::Dependency> {
+        Vec::new()
+    }
 }
 
-impl Context for Moonbase {}
+impl Context for Moonbase {
+    fn module_loading<M: module::Module<Self>>(&self) -> anyhow::Result<()> {
+        if self.is_module_loaded(&std::any::TypeId::of::<M>()) {
+            let module = M::module_name();
+            return Err(module::ModuleLoadError::AlreadyLoaded { module }.into());
+        }
+        Ok(())
+    }
+    fn module_loaded<M: module::Module<Self>>(&self) -> anyhow::Result<()> {
+        Ok(self.register_module::<M>()?)
+    }
+}
 
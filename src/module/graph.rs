@@ -0,0 +1,379 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use crossbeam::sync::ShardedLock;
+use futures::{future::BoxFuture, FutureExt};
+
+use crate::Moonbase;
+
+use super::Module;
+
+/// Something a [`Module`] needs before it can be initialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dependency {
+    Module { type_id: TypeId, name: &'static str },
+    Resource { type_id: TypeId, name: &'static str },
+}
+
+impl Dependency {
+    pub fn module<M: Any>() -> Self {
+        Dependency::Module {
+            type_id: TypeId::of::<M>(),
+            name: std::any::type_name::<M>(),
+        }
+    }
+    pub fn resource<R: Any>() -> Self {
+        Dependency::Resource {
+            type_id: TypeId::of::<R>(),
+            name: std::any::type_name::<R>(),
+        }
+    }
+    pub fn type_id(&self) -> TypeId {
+        match self {
+            Dependency::Module { type_id, .. } | Dependency::Resource { type_id, .. } => *type_id,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dependency::Module { name, .. } | Dependency::Resource { name, .. } => name,
+        }
+    }
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dependency::Module { name, .. } => write!(f, "module `{name}`"),
+            Dependency::Resource { name, .. } => write!(f, "resource `{name}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleDescriptor {
+    pub name: &'static str,
+    pub type_id: TypeId,
+    pub dependencies: Vec<Dependency>,
+    pub provides: Vec<Dependency>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MissingDependency {
+    pub module: &'static str,
+    pub dependency: Dependency,
+}
+
+#[derive(Debug)]
+pub enum ModuleLoadError {
+    /// the same module type was added more than once
+    Duplicated { module: &'static str },
+    /// the module is already loaded in the context
+    AlreadyLoaded { module: &'static str },
+    /// some dependencies are neither in the graph nor in the context
+    Missing(Vec<MissingDependency>),
+    /// modules depend on each other, the path starts and ends with the same module
+    Cycle(Vec<&'static str>),
+    /// a module failed in [`Module::initialize`]
+    Initialize {
+        module: &'static str,
+        source: anyhow::Error,
+    },
+    /// a module didn't set a resource it declares in [`Module::provides`]
+    NotProvided {
+        module: &'static str,
+        dependency: Dependency,
+    },
+}
+
+impl std::fmt::Display for ModuleLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleLoadError::Duplicated { module } => {
+                write!(f, "module `{module}` is added more than once")
+            }
+            ModuleLoadError::AlreadyLoaded { module } => {
+                write!(f, "module `{module}` is already loaded")
+            }
+            ModuleLoadError::Missing(missing) => {
+                write!(f, "missing dependencies:")?;
+                for MissingDependency { module, dependency } in missing {
+                    write!(f, "\n  module `{module}` requires {dependency}")?;
+                }
+                Ok(())
+            }
+            ModuleLoadError::Cycle(path) => {
+                write!(f, "dependency cycle detected: {}", path.join(" -> "))
+            }
+            ModuleLoadError::Initialize { module, source } => {
+                write!(f, "fail to initialize module `{module}`: {source}")
+            }
+            ModuleLoadError::NotProvided { module, dependency } => {
+                write!(f, "module `{module}` doesn't set {dependency} it provides")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModuleLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModuleLoadError::Initialize { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+type ModuleInitializer = Box<dyn FnOnce(Moonbase) -> BoxFuture<'static, anyhow::Result<()>> + Send>;
+type ModuleFinalizer = fn(Moonbase) -> BoxFuture<'static, anyhow::Result<()>>;
+
+fn finalize_module<M: Module<Moonbase>>(context: Moonbase) -> BoxFuture<'static, anyhow::Result<()>> {
+    M::finalize(context).boxed()
+}
+
+struct ModuleEntry {
+    descriptor: ModuleDescriptor,
+    initializer: ModuleInitializer,
+    finalizer: ModuleFinalizer,
+}
+
+impl std::fmt::Debug for ModuleEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleEntry")
+            .field("descriptor", &self.descriptor)
+            .finish()
+    }
+}
+
+/// A set of modules which will be loaded in the order of their dependencies.
+#[derive(Debug, Default)]
+pub struct ModuleGraph {
+    entries: Vec<ModuleEntry>,
+}
+
+impl ModuleGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<M: Module<Moonbase>>(&mut self, module: M) -> &mut Self {
+        self.entries.push(ModuleEntry {
+            descriptor: M::descriptor(),
+            initializer: Box::new(move |context| module.initialize(context).boxed()),
+            finalizer: finalize_module::<M>,
+        });
+        self
+    }
+
+    pub fn descriptors(&self) -> impl Iterator<Item = &ModuleDescriptor> + '_ {
+        self.entries.iter().map(|entry| &entry.descriptor)
+    }
+
+    /// Sort the modules topologically, the returned indexes are in load order.
+    ///
+    /// A dependency is satisfied if it's provided by another module in the graph,
+    /// or if it's already present in the context.
+    pub fn resolve(&self, context: &Moonbase) -> Result<Vec<usize>, ModuleLoadError> {
+        let mut index_of = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index_of.insert(entry.descriptor.type_id, index).is_some() {
+                return Err(ModuleLoadError::Duplicated {
+                    module: entry.descriptor.name,
+                });
+            }
+            if context.is_module_loaded(&entry.descriptor.type_id) {
+                return Err(ModuleLoadError::AlreadyLoaded {
+                    module: entry.descriptor.name,
+                });
+            }
+        }
+        let mut providers = HashMap::<TypeId, Vec<usize>>::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            for provided in &entry.descriptor.provides {
+                providers.entry(provided.type_id()).or_default().push(index);
+            }
+        }
+        let mut missing = Vec::new();
+        let mut dependents = vec![Vec::<usize>::new(); self.entries.len()];
+        let mut in_degree = vec![0usize; self.entries.len()];
+        for (index, entry) in self.entries.iter().enumerate() {
+            for dependency in &entry.descriptor.dependencies {
+                let found = match dependency {
+                    Dependency::Module { type_id, .. } => index_of
+                        .get(type_id)
+                        .map(|index| vec![*index])
+                        .or_else(|| context.is_module_loaded(type_id).then(Vec::new)),
+                    Dependency::Resource { type_id, .. } => providers
+                        .get(type_id)
+                        .cloned()
+                        .or_else(|| context.has_resource_type_id(type_id).then(Vec::new)),
+                };
+                match found {
+                    Some(dependencies) => {
+                        // depending on itself, directly or by a resource it provides
+                        if dependencies.contains(&index) {
+                            let name = entry.descriptor.name;
+                            return Err(ModuleLoadError::Cycle(vec![name, name]));
+                        }
+                        for dependency in dependencies {
+                            dependents[dependency].push(index);
+                            in_degree[index] += 1;
+                        }
+                    }
+                    None => missing.push(MissingDependency {
+                        module: entry.descriptor.name,
+                        dependency: *dependency,
+                    }),
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(ModuleLoadError::Missing(missing));
+        }
+        let mut queue = (0..self.entries.len())
+            .filter(|index| in_degree[*index] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(self.entries.len());
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+            for dependent in &dependents[index] {
+                in_degree[*dependent] -= 1;
+                if in_degree[*dependent] == 0 {
+                    queue.push_back(*dependent);
+                }
+            }
+        }
+        if order.len() < self.entries.len() {
+            return Err(ModuleLoadError::Cycle(
+                self.find_cycle(&dependents, &in_degree),
+            ));
+        }
+        Ok(order)
+    }
+
+    /// walk along the unresolved dependencies until some module is visited twice
+    fn find_cycle(&self, dependents: &[Vec<usize>], in_degree: &[usize]) -> Vec<&'static str> {
+        let mut dependencies = vec![Vec::new(); self.entries.len()];
+        for (index, dependents) in dependents.iter().enumerate() {
+            for dependent in dependents {
+                dependencies[*dependent].push(index);
+            }
+        }
+        let start = in_degree
+            .iter()
+            .position(|degree| *degree > 0)
+            .expect("cycle exists");
+        let mut path = vec![start];
+        let mut current = start;
+        loop {
+            current = *dependencies[current]
+                .iter()
+                .find(|dependency| in_degree[**dependency] > 0)
+                .expect("unresolved module has unresolved dependency");
+            if let Some(position) = path.iter().position(|index| *index == current) {
+                let mut cycle = path.split_off(position);
+                cycle.push(current);
+                return cycle
+                    .into_iter()
+                    .map(|index| self.entries[index].descriptor.name)
+                    .collect();
+            }
+            path.push(current);
+        }
+    }
+
+    /// Resolve the graph and initialize every module in order.
+    pub async fn load(self, context: &Moonbase) -> Result<(), ModuleLoadError> {
+        let order = self.resolve(context)?;
+        let mut entries = self.entries.into_iter().map(Some).collect::<Vec<_>>();
+        for index in order {
+            let ModuleEntry {
+                descriptor,
+                initializer,
+                finalizer,
+            } = entries[index].take().expect("each module is loaded once");
+            initializer(context.clone())
+                .await
+                .map_err(|source| ModuleLoadError::Initialize {
+                    module: descriptor.name,
+                    source,
+                })?;
+            context.register_loaded(LoadedModule {
+                descriptor,
+                finalizer,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadedModule {
+    descriptor: ModuleDescriptor,
+    finalizer: ModuleFinalizer,
+}
+
+pub type ModuleRegistry = Arc<ShardedLock<Vec<LoadedModule>>>;
+
+impl Moonbase {
+    /// Load a set of modules in the order of their dependencies.
+    pub async fn load_modules(&self, modules: ModuleGraph) -> Result<(), ModuleLoadError> {
+        modules.load(self).await
+    }
+    /// Register a module initialized outside of a [`ModuleGraph`], so it's finalized too.
+    pub(crate) fn register_module<M: Module<Moonbase>>(&self) -> Result<(), ModuleLoadError> {
+        self.register_loaded(LoadedModule {
+            descriptor: M::descriptor(),
+            finalizer: finalize_module::<M>,
+        })
+    }
+    /// Register an initialized module, then check it set the resources it provides.
+    ///
+    /// It's registered even if it doesn't, so what it did set is finalized.
+    fn register_loaded(&self, module: LoadedModule) -> Result<(), ModuleLoadError> {
+        let name = module.descriptor.name;
+        let provides = module.descriptor.provides.clone();
+        let not_provided = provides
+            .into_iter()
+            .find(|provided| !self.has_resource_type_id(&provided.type_id()));
+        self.modules.write().unwrap().push(module);
+        match not_provided {
+            Some(dependency) => Err(ModuleLoadError::NotProvided {
+                module: name,
+                dependency,
+            }),
+            None => Ok(()),
+        }
+    }
+    /// Descriptors of modules loaded by [`Moonbase::load_modules`] or
+    /// [`load_module`](crate::context::ContextExt::load_module), in load order.
+    pub fn loaded_modules(&self) -> Vec<ModuleDescriptor> {
+        let modules = self.modules.read().unwrap();
+        modules
+            .iter()
+            .map(|module| module.descriptor.clone())
+            .collect()
+    }
+    pub fn is_module_loaded(&self, type_id: &TypeId) -> bool {
+        let modules = self.modules.read().unwrap();
+        modules
+            .iter()
+            .any(|module| module.descriptor.type_id == *type_id)
+    }
+    /// Call [`Module::finalize`] of every loaded module in reverse load order.
+    ///
+    /// Modules are unregistered even if they fail to finalize, the errors are returned
+    /// together with the module names.
+    pub async fn finalize_modules(&self) -> Vec<(&'static str, anyhow::Error)> {
+        let modules = std::mem::take(&mut *self.modules.write().unwrap());
+        let mut errors = Vec::new();
+        for module in modules.into_iter().rev() {
+            if let Err(error) = (module.finalizer)(self.clone()).await {
+                errors.push((module.descriptor.name, error));
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,57 @@
+use std::any;
+
+use crate::{
+    context::Context,
+    handler::{Adapter, Handler},
+};
+mod graph;
+pub use graph::*;
+
+pub trait Module<C: Context>: Send + 'static {
+    fn module_name() -> &'static str {
+        any::type_name::<Self>()
+    }
+    /// Modules and resources which must be ready before this module is initialized.
+    fn dependencies() -> Vec<Dependency> {
+        Vec::new()
+    }
+    /// Resources this module sets into the context while initializing.
+    fn provides() -> Vec<Dependency> {
+        Vec::new()
+    }
+    fn descriptor() -> ModuleDescriptor {
+        ModuleDescriptor {
+            name: Self::module_name(),
+            type_id: any::TypeId::of::<Self>(),
+            dependencies: Self::dependencies(),
+            provides: Self::provides(),
+        }
+    }
+    fn initialize(self, context: C)
+        -> impl std::future::Future<Output = anyhow::Result<()>> + Send;
+    /// Release what the module holds in the context, called in reverse load order on shutdown.
+    fn finalize(context: C) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
+        drop(context);
+        async { Ok(()) }
+    }
+}
+
+pub struct ModuleAdapter<M: Module<C>, C: Context> {
+    marker: std::marker::PhantomData<fn(C) -> M>,
+}
+
+impl<M: Module<C>, C: Context> Adapter for ModuleAdapter<M, C> {
+    type Args = C;
+    type Ret = anyhow::Result<()>;
+}
+
+impl<M, C> Handler<ModuleAdapter<M, C>> for M
+where
+    M: Module<C> + Send,
+    C: Context,
+{
+    async fn apply(self, context: C) -> anyhow::Result<()> {
+        let fut = self.initialize(context);
+        fut.await
+    }
+}
@@ -0,0 +1,96 @@
+use std::{any::TypeId, collections::HashSet, sync::Arc};
+
+use crate::Moonbase;
+
+/// Why a resource can't be got, see [`Moonbase::get_or_init_resource`].
+#[derive(Debug, Clone)]
+pub enum ResourceError {
+    NotFound(ResourceNotFound),
+    /// the factory of a lazy resource failed
+    InitFailed {
+        type_name: &'static str,
+        error: Arc<anyhow::Error>,
+    },
+}
+
+impl std::fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceError::NotFound(not_found) => not_found.fmt(f),
+            ResourceError::InitFailed { type_name, error } => {
+                write!(f, "fail to initialize resource `{type_name}`: {error:#}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+/// Neither the resource nor its factory is set in the context or its parents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceNotFound {
+    pub type_name: &'static str,
+    /// ids of the contexts searched, from the one looked up in to the root
+    pub contexts: Vec<u64>,
+    /// registered resource types sharing a type name with the missing one
+    pub similar: Vec<&'static str>,
+}
+
+impl ResourceNotFound {
+    pub(crate) fn new<T: 'static>(context: &Moonbase) -> Self {
+        let type_name = std::any::type_name::<T>();
+        let mut contexts = vec![context.id()];
+        let mut parent = context.parent();
+        while let Some(context) = parent {
+            contexts.push(context.id());
+            parent = context.parent();
+        }
+        let wanted = identifiers(type_name).collect::<HashSet<_>>();
+        let similar = context
+            .resource_types()
+            .into_iter()
+            .filter(|(id, _)| *id != TypeId::of::<T>())
+            .map(|(_, name)| name)
+            .filter(|name| identifiers(name).any(|ident| wanted.contains(&ident)))
+            .collect();
+        Self {
+            type_name,
+            contexts,
+            similar,
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "resource `{}` not found in moonbase", self.type_name)?;
+        for (index, id) in self.contexts.iter().enumerate() {
+            let separator = if index == 0 { " " } else { " -> " };
+            write!(f, "{separator}#{id}")?;
+        }
+        if !self.similar.is_empty() {
+            write!(f, ", similar registered types:")?;
+            for name in &self.similar {
+                write!(f, " `{name}`")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResourceNotFound {}
+
+/// Wrappers too common to tell two types are alike.
+const WRAPPERS: &[&str] = &[
+    "Arc", "Rc", "Box", "Option", "Vec", "Mutex", "RwLock", "Cell", "RefCell", "Watch",
+];
+
+/// Lowercase names in a type name without paths and wrappers, e.g. `pool` in
+/// `alloc::sync::Arc<app::db::Pool>`.
+fn identifiers(type_name: &str) -> impl Iterator<Item = String> + '_ {
+    type_name
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .filter_map(|path| path.rsplit("::").next())
+        .filter(|ident| !ident.is_empty() && !WRAPPERS.contains(ident))
+        .map(str::to_lowercase)
+}
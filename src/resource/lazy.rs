@@ -0,0 +1,121 @@
+use std::{
+    any::{Any, TypeId},
+    future::Future,
+    sync::Arc,
+};
+
+use futures::{
+    future::{BoxFuture, Shared},
+    FutureExt,
+};
+
+use crate::Moonbase;
+
+use super::{MoonbaseResource, ResourceError, ResourceNotFound};
+
+type ResourceFactory<T> =
+    Arc<dyn Fn(Moonbase) -> BoxFuture<'static, anyhow::Result<T>> + Send + Sync>;
+
+type Initializing<T> = Shared<BoxFuture<'static, Result<Arc<T>, Arc<anyhow::Error>>>>;
+
+/// A resource built by a factory on first use, see [`Moonbase::set_resource_factory`].
+pub(crate) struct LazyResource<T> {
+    factory: ResourceFactory<T>,
+    initializing: Option<Initializing<T>>,
+}
+
+impl<T: Any + Send + Sync> LazyResource<T> {
+    /// The ongoing initialization, started if there's none.
+    fn initialize(&mut self, context: &Moonbase) -> Initializing<T> {
+        let factory = self.factory.clone();
+        let context = context.clone();
+        self.initializing
+            .get_or_insert_with(|| {
+                async move { factory(context).await.map(Arc::new).map_err(Arc::new) }
+                    .boxed()
+                    .shared()
+            })
+            .clone()
+    }
+}
+
+impl Moonbase {
+    /// Register an async factory building the resource on first
+    /// [`get_or_init_resource`](Moonbase::get_or_init_resource).
+    ///
+    /// The factory gets this context, concurrent callers await the same initialization, and the
+    /// built resource is cached. If it fails, the next call tries again.
+    pub fn set_resource_factory<T, F, Fut>(&self, factory: F)
+    where
+        T: Any + Send + Sync,
+        F: Fn(Moonbase) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        let lazy = LazyResource {
+            factory: Arc::new(move |context| factory(context).boxed()),
+            initializing: None,
+        };
+        let mut resources = self.resources.write().unwrap();
+        let id = TypeId::of::<T>();
+        resources.type_names.insert(id, std::any::type_name::<T>());
+        resources.factories.insert(id, Box::new(lazy));
+    }
+    pub fn remove_resource_factory<T: Any + Send + Sync>(&self) -> bool {
+        let mut resources = self.resources.write().unwrap();
+        resources.factories.remove(&TypeId::of::<T>()).is_some()
+    }
+    /// Get the resource, building it by its factory if it's not built yet.
+    ///
+    /// Falls back to the parent context if neither the resource nor its factory is set here.
+    pub async fn get_or_init_resource<T: MoonbaseResource>(&self) -> Result<T, ResourceError> {
+        let resource = self.get_or_init_resource_arc::<T>().await?;
+        Ok(T::clone(&resource))
+    }
+    /// Same as [`get_or_init_resource`](Moonbase::get_or_init_resource), without cloning the
+    /// resource.
+    pub async fn get_or_init_resource_arc<T: Any + Send + Sync>(
+        &self,
+    ) -> Result<Arc<T>, ResourceError> {
+        let mut context = self;
+        loop {
+            if let Some(result) = context.get_or_init_local_resource::<T>().await {
+                return result;
+            }
+            match context.parent() {
+                Some(parent) => context = parent,
+                None => return Err(ResourceError::NotFound(ResourceNotFound::new::<T>(self))),
+            }
+        }
+    }
+    /// `None` if neither the resource nor its factory is set in this context.
+    async fn get_or_init_local_resource<T: Any + Send + Sync>(
+        &self,
+    ) -> Option<Result<Arc<T>, ResourceError>> {
+        let initializing = {
+            let mut resources = self.resources.write().unwrap();
+            if let Some(resource) = resources.get_arc::<T>() {
+                return Some(Ok(resource));
+            }
+            let lazy = resources.factories.get_mut(&TypeId::of::<T>())?;
+            lazy.downcast_mut::<LazyResource<T>>()
+                .expect("type mismatch")
+                .initialize(self)
+        };
+        let result = initializing.await;
+        let mut resources = self.resources.write().unwrap();
+        // the first awaiter to finish caches the resource
+        let first = resources
+            .factories
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|lazy| lazy.downcast_mut::<LazyResource<T>>())
+            .is_some_and(|lazy| lazy.initializing.take().is_some());
+        if let (true, Ok(resource)) = (first, &result) {
+            resources.insert_arc(resource.clone());
+        }
+        drop(resources);
+        Some(result.map_err(|error| ResourceError::InitFailed {
+            type_name: std::any::type_name::<T>(),
+            error,
+        }))
+    }
+}
@@ -7,7 +7,19 @@ use std::{
 
 use crossbeam::sync::ShardedLock;
 // e.g. signal -> config -> web server
-use crate::{extract::ExtractFrom, Moonbase};
+use crate::{
+    extract::{ExtractFrom, TryExtractFrom},
+    module::Dependency,
+    Moonbase,
+};
+
+mod error;
+mod lazy;
+mod watch;
+
+pub use error::*;
+pub use watch::*;
+
 pub trait MoonbaseResource: Send + Sync + Any + Clone {}
 impl<T> MoonbaseResource for T 
 where 
@@ -39,13 +51,16 @@ impl<T> Resource<T> {
     }
 }
 
-impl<T> ExtractFrom<Moonbase> for Resource<T>
+impl<T> TryExtractFrom<Moonbase> for Resource<T>
 where
     T: MoonbaseResource,
 {
-    async fn extract_from(context: &Moonbase) -> Self {
-        let resource = context.get_resource::<T>().expect("fail to get resource");
-        Resource(resource)
+    type Error = ResourceError;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        context.get_or_init_resource::<T>().await.map(Resource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<T>()]
     }
 }
 
@@ -54,68 +69,183 @@ where
     T: MoonbaseResource,
 {
     async fn extract_from(context: &Moonbase) -> Self {
-        context.get_resource::<T>().map(Resource)
+        context.get_or_init_resource::<T>().await.ok().map(Resource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+/// A resource extracted without cloning it, the resource doesn't need to be [`Clone`].
+#[derive(Debug)]
+pub struct SharedResource<T>(pub Arc<T>);
+
+impl<T> Clone for SharedResource<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> AsRef<T> for SharedResource<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Deref for SharedResource<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> SharedResource<T> {
+    pub fn into_inner(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> TryExtractFrom<Moonbase> for SharedResource<T>
+where
+    T: Any + Send + Sync,
+{
+    type Error = ResourceError;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        let resource = context.get_or_init_resource_arc::<T>().await;
+        resource.map(SharedResource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<T>()]
+    }
+}
+
+impl<T> ExtractFrom<Moonbase> for Option<SharedResource<T>>
+where
+    T: Any + Send + Sync,
+{
+    async fn extract_from(context: &Moonbase) -> Self {
+        let resource = context.get_or_init_resource_arc::<T>().await;
+        resource.ok().map(SharedResource)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
     }
 }
 
 #[derive(Debug, Default)]
 pub struct ResourceRepositoryInner {
-    components: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    components: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    /// lazy resources not built yet, or which can be built again after removed
+    factories: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    /// names of every type ever registered
+    type_names: HashMap<TypeId, &'static str>,
 }
 
 pub type ResourceRepository = Arc<ShardedLock<ResourceRepositoryInner>>;
 
+fn downcast<T: Any + Send + Sync>(resource: Arc<dyn Any + Send + Sync>) -> Arc<T> {
+    Arc::downcast(resource).expect("type mismatch")
+}
+
 impl ResourceRepositoryInner {
     pub fn new() -> Self {
         ResourceRepositoryInner::default()
     }
 
     pub fn insert<T: MoonbaseResource>(&mut self, component: T) -> Option<T> {
+        self.insert_arc(Arc::new(component))
+            .map(Arc::unwrap_or_clone)
+    }
+
+    pub fn insert_arc<T: Any + Send + Sync>(&mut self, component: Arc<T>) -> Option<Arc<T>> {
         let id = TypeId::of::<T>();
-        let replaced = self.components.insert(id, Box::new(component));
-        replaced.map(|component| *component.downcast::<T>().expect("type mismatch").clone())
+        self.type_names.insert(id, std::any::type_name::<T>());
+        self.components.insert(id, component).map(downcast)
     }
 
     pub fn remove<T: MoonbaseResource>(&mut self) -> Option<T> {
-        let id = TypeId::of::<T>();
+        self.remove_arc::<T>().map(Arc::unwrap_or_clone)
+    }
 
-        self.components
-            .remove(&id)
-            .map(|component| *component.downcast::<T>().expect("type mismatch").clone())
+    pub fn remove_arc<T: Any + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        let id = TypeId::of::<T>();
+        self.components.remove(&id).map(downcast)
     }
 
     pub fn get<T: MoonbaseResource>(&self) -> Option<T> {
+        self.get_arc::<T>().map(|component| T::clone(&component))
+    }
+
+    pub fn get_arc<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
         let id = TypeId::of::<T>();
+        self.components.get(&id).cloned().map(downcast)
+    }
 
-        self.components.get(&id).map(|component| {
-            component
-                .downcast_ref::<T>()
-                .cloned()
-                .expect("type mismatch")
-        })
+    /// Whether the resource is set, or can be built by a factory.
+    pub fn has<T: Any>(&self) -> bool {
+        self.has_type_id(&TypeId::of::<T>())
     }
 
-    pub fn has<T: MoonbaseResource>(&self) -> bool {
-        let id = TypeId::of::<T>();
-        self.components.contains_key(&id)
+    pub fn has_type_id(&self, id: &TypeId) -> bool {
+        self.components.contains_key(id) || self.factories.contains_key(id)
+    }
+
+    /// Types of resources set, or which can be built by a factory.
+    pub fn types(&self) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.type_names
+            .iter()
+            .filter(|(id, _)| self.has_type_id(id))
+            .map(|(id, name)| (*id, *name))
     }
 }
 
 impl Moonbase {
     pub fn set_resource<T: MoonbaseResource>(&self, resource: T) {
+        self.set_resource_arc(Arc::new(resource));
+    }
+    /// Set a resource already shared, or which is not [`Clone`].
+    pub fn set_resource_arc<T: Any + Send + Sync>(&self, resource: Arc<T>) {
         let mut resources = self.resources.write().unwrap();
-        resources.insert(resource);
+        resources.insert_arc(resource);
     }
+    /// Falls back to the parent context if it's not set here.
+    ///
+    /// Lazy resources not built yet are not returned, see
+    /// [`get_or_init_resource`](Moonbase::get_or_init_resource).
     pub fn get_resource<T: MoonbaseResource>(&self) -> Option<T> {
-        let resources = self.resources.read().unwrap();
-        resources.get::<T>()
+        self.get_resource_arc::<T>()
+            .map(|resource| T::clone(&resource))
+    }
+    /// Same as [`get_resource`](Moonbase::get_resource), without cloning the resource.
+    pub fn get_resource_arc<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let resource = self.resources.read().unwrap().get_arc::<T>();
+        resource.or_else(|| self.parent()?.get_resource_arc::<T>())
     }
+    /// Only removes the resource set in this context.
     pub fn remove_resource<T: MoonbaseResource>(&self) -> Option<T> {
+        self.remove_resource_arc::<T>().map(Arc::unwrap_or_clone)
+    }
+    pub fn remove_resource_arc<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
         let mut resources = self.resources.write().unwrap();
-        resources.remove::<T>()
+        resources.remove_arc::<T>()
+    }
+    pub fn has_resource<T: Any>(&self) -> bool {
+        self.has_resource_type_id(&TypeId::of::<T>())
+    }
+    pub fn has_resource_type_id(&self, id: &TypeId) -> bool {
+        let has = self.resources.read().unwrap().has_type_id(id);
+        has || self
+            .parent()
+            .is_some_and(|parent| parent.has_resource_type_id(id))
     }
-    pub fn has_resource<T: MoonbaseResource>(&self) -> bool {
-        let resources = self.resources.read().unwrap();
-        resources.has::<T>()
+    /// Types of resources available in this context and its parents, sorted by name.
+    pub fn resource_types(&self) -> Vec<(TypeId, &'static str)> {
+        let mut types = self.resources.read().unwrap().types().collect::<Vec<_>>();
+        if let Some(parent) = self.parent() {
+            types.extend(parent.resource_types());
+        }
+        types.sort_by_key(|(_, name)| *name);
+        types.dedup();
+        types
     }
 }
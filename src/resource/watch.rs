@@ -0,0 +1,152 @@
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use arc_swap::ArcSwap;
+
+use crate::{
+    signal::{Signal, SignalKey},
+    Moonbase,
+};
+
+/// A hot-swappable resource, e.g. a config or a TLS certificate changing at runtime.
+///
+/// Readers get the current value without locking, and [`Watcher`]s are notified when it's
+/// [`replace`](Watch::replace)d.
+pub struct Watch<T> {
+    inner: Arc<WatchInner<T>>,
+}
+
+struct WatchInner<T> {
+    value: ArcSwap<T>,
+    version: AtomicU64,
+    signal: Signal,
+}
+
+impl<T> Clone for Watch<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Watch<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watch")
+            .field("value", &self.inner.value.load())
+            .field("version", &self.version())
+            .finish()
+    }
+}
+
+impl<T> Watch<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Arc::new(WatchInner {
+                value: ArcSwap::from_pointee(value),
+                version: AtomicU64::new(0),
+                signal: Signal::new(),
+            }),
+        }
+    }
+    pub fn load(&self) -> Arc<T> {
+        self.inner.value.load_full()
+    }
+    /// Swap in a new value and notify watchers, returns the previous one.
+    pub fn replace(&self, value: T) -> Arc<T> {
+        let previous = self.inner.value.swap(Arc::new(value));
+        self.inner.version.fetch_add(1, Ordering::SeqCst);
+        self.inner.signal.get_sender().send();
+        previous
+    }
+    /// How many times the value is replaced.
+    pub fn version(&self) -> u64 {
+        self.inner.version.load(Ordering::SeqCst)
+    }
+    /// Triggered on every replacement.
+    pub fn signal(&self) -> Signal {
+        self.inner.signal.clone()
+    }
+    /// Watch changes from now on.
+    pub fn watch(&self) -> Watcher<T> {
+        Watcher {
+            watch: self.clone(),
+            seen: self.version(),
+        }
+    }
+}
+
+/// Awaits the changes of a [`Watch`].
+pub struct Watcher<T> {
+    watch: Watch<T>,
+    seen: u64,
+}
+
+impl<T> Clone for Watcher<T> {
+    fn clone(&self) -> Self {
+        Self {
+            watch: self.watch.clone(),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Watcher<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watcher")
+            .field("watch", &self.watch)
+            .field("seen", &self.seen)
+            .finish()
+    }
+}
+
+impl<T> Watcher<T> {
+    pub fn current(&self) -> Arc<T> {
+        self.watch.load()
+    }
+    /// Wait until the value is replaced, returns the latest one.
+    ///
+    /// Replacements made since the last call are coalesced into one change.
+    pub async fn changed(&mut self) -> Arc<T> {
+        loop {
+            // created before checking the version, so no replacement is missed
+            let waiting = self.watch.inner.signal.recv();
+            let version = self.watch.version();
+            if version != self.seen {
+                self.seen = version;
+                return self.watch.load();
+            }
+            waiting.await;
+        }
+    }
+}
+
+impl Moonbase {
+    /// Set a [`Watch`] resource, or replace the value of the one set in this context.
+    ///
+    /// Its signal is registered under `SignalKey::symbol::<Watch<T>>()`.
+    pub fn set_watch_resource<T: Send + Sync + Any>(&self, value: T) -> Watch<T> {
+        // looked up and inserted under one lock, so concurrent callers share the same watch
+        let mut resources = self.resources.write().unwrap();
+        if let Some(watch) = resources.get::<Watch<T>>() {
+            drop(resources);
+            watch.replace(value);
+            return watch;
+        }
+        let watch = Watch::new(value);
+        resources.insert(watch.clone());
+        self.set_signal(SignalKey::symbol::<Watch<T>>(), watch.signal());
+        watch
+    }
+    pub fn get_watch_resource<T: Send + Sync + Any>(&self) -> Option<Watch<T>> {
+        self.get_resource::<Watch<T>>()
+    }
+    pub fn watch_resource<T: Send + Sync + Any>(&self) -> Option<Watcher<T>> {
+        self.get_watch_resource::<T>().map(|watch| watch.watch())
+    }
+}
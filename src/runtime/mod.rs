@@ -1,13 +1,188 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::{
+    future::{select, BoxFuture, Either},
+    Stream,
+};
+
+use crate::Moonbase;
+
 #[cfg(feature = "rt-tokio")]
 mod tokio;
 #[cfg(feature = "rt-tokio")]
-pub use tokio::Tokio;
+pub use tokio::{terminate_signal, Tokio};
+
+#[cfg(feature = "rt-smol")]
+mod smol;
+#[cfg(feature = "rt-smol")]
+pub use smol::Smol;
+
+mod task;
+pub use task::{Elapsed, JoinError, JoinHandle};
+mod testing;
+pub use testing::TestRuntime;
 
 #[cfg(feature = "rt-tokio")]
 pub type DefaultRuntime = Tokio;
+#[cfg(all(feature = "rt-smol", not(feature = "rt-tokio")))]
+pub type DefaultRuntime = Smol;
 pub trait Runtime {
-    fn spawn<F>(&self, future: F)
+    /// Run `future` in background, every other way to spawn is built on it.
+    fn spawn_detached<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static;
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send;
+    /// Spawn `future`, the returned handle can join or abort it.
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = JoinHandle::task(future);
+        self.spawn_detached(task);
+        handle
+    }
+    /// Run blocking `job` where it won't block other tasks, by default on a new thread.
+    fn spawn_blocking<F, T>(&self, job: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = JoinHandle::blocking(job);
+        std::thread::spawn(job);
+        handle
+    }
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+    /// Resolves with the output of `future`, or [`Elapsed`] if it doesn't finish in `duration`.
+    fn timeout<F>(
+        &self,
+        duration: Duration,
+        future: F,
+    ) -> impl Future<Output = Result<F::Output, Elapsed>> + Send
+    where
+        F: Future + Send,
+    {
+        let sleep = self.sleep(duration);
+        async move {
+            match select(pin!(future), pin!(sleep)).await {
+                Either::Left((output, _)) => Ok(output),
+                Either::Right(_) => Err(Elapsed),
+            }
+        }
+    }
+    /// Yields the time of each tick, the first tick is immediate and the later ones are
+    /// `period` apart. Late ticks are yielded at once, so the tick count keeps up with time.
+    fn interval(&self, period: Duration) -> impl Stream<Item = Instant> + Send
+    where
+        Self: Sync,
+    {
+        futures::stream::unfold(self.now(), move |tick| async move {
+            let now = self.now();
+            if tick > now {
+                self.sleep(tick - now).await;
+            }
+            Some((tick, tick + period))
+        })
+    }
+}
+
+/// The object safe version of [`Runtime`], implemented for every runtime.
+pub trait DynRuntime: Send + Sync + Debug + 'static {
+    fn spawn_boxed(&self, future: BoxFuture<'static, ()>);
+    fn spawn_blocking_boxed(&self, job: Box<dyn FnOnce() + Send>);
+    fn sleep_boxed(&self, duration: Duration) -> BoxFuture<'_, ()>;
+    fn now(&self) -> Instant;
+}
+
+impl<R> DynRuntime for R
+where
+    R: Runtime + Send + Sync + Debug + 'static,
+{
+    fn spawn_boxed(&self, future: BoxFuture<'static, ()>) {
+        self.spawn_detached(future)
+    }
+    fn spawn_blocking_boxed(&self, job: Box<dyn FnOnce() + Send>) {
+        // the runtime's own handle is dropped, the job reports through its wrapper
+        drop(self.spawn_blocking(job));
+    }
+    fn sleep_boxed(&self, duration: Duration) -> BoxFuture<'_, ()> {
+        Box::pin(self.sleep(duration))
+    }
+    fn now(&self) -> Instant {
+        Runtime::now(self)
+    }
+}
+
+/// The runtime a moonbase is configured with, see [`Moonbase::set_runtime`].
+#[derive(Debug, Clone)]
+pub struct RuntimeHandle {
+    inner: Arc<dyn DynRuntime>,
+}
+
+impl RuntimeHandle {
+    pub fn new<R: DynRuntime>(runtime: R) -> Self {
+        Self {
+            inner: Arc::new(runtime),
+        }
+    }
+}
+
+impl Runtime for RuntimeHandle {
+    fn spawn_detached<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.inner.spawn_boxed(Box::pin(future))
+    }
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        self.inner.sleep_boxed(duration)
+    }
+    fn spawn_blocking<F, T>(&self, job: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = JoinHandle::blocking(job);
+        self.inner.spawn_blocking_boxed(Box::new(job));
+        handle
+    }
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+}
+
+/// No runtime is set for the moonbase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeNotSet;
+
+impl std::fmt::Display for RuntimeNotSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "no runtime set, load a runtime module or call `Moonbase::set_runtime`"
+        )
+    }
+}
+
+impl std::error::Error for RuntimeNotSet {}
+
+impl Moonbase {
+    /// Set the runtime to spawn daemons and tasks with, replacing the previous one.
+    pub fn set_runtime<R>(&self, runtime: R)
     where
-        F: std::future::Future<Output = ()> + Send + 'static;
-    fn sleep(&self, duration: std::time::Duration) -> impl std::future::Future<Output = ()> + Send;
+        R: Runtime + Send + Sync + Debug + 'static,
+    {
+        self.set_resource(RuntimeHandle::new(runtime));
+    }
+    pub fn runtime(&self) -> Result<RuntimeHandle, RuntimeNotSet> {
+        self.get_resource::<RuntimeHandle>().ok_or(RuntimeNotSet)
+    }
 }
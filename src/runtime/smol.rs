@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use crate::{
+    module::{Dependency, Module},
+    Moonbase,
+};
+
+/// A smol runtime, spawning on the global smol executor unless an executor is given.
+#[derive(Debug, Clone, Default)]
+pub struct Smol {
+    pub executor: Option<Arc<smol::Executor<'static>>>,
+}
+
+impl Smol {
+    pub fn new(executor: Arc<smol::Executor<'static>>) -> Self {
+        Self {
+            executor: Some(executor),
+        }
+    }
+}
+
+impl super::Runtime for Smol {
+    fn spawn_detached<F>(&self, future: F)
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        match &self.executor {
+            Some(executor) => executor.spawn(future).detach(),
+            None => smol::spawn(future).detach(),
+        }
+    }
+
+    fn spawn_blocking<F, T>(&self, job: F) -> super::JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = super::JoinHandle::blocking(job);
+        smol::unblock(job).detach();
+        handle
+    }
+
+    fn sleep(&self, duration: std::time::Duration) -> impl std::future::Future<Output = ()> + Send {
+        let timer = smol::Timer::after(duration);
+        async move {
+            timer.await;
+        }
+    }
+}
+
+impl Module<Moonbase> for Smol {
+    fn provides() -> Vec<Dependency> {
+        vec![
+            Dependency::resource::<Smol>(),
+            Dependency::resource::<super::RuntimeHandle>(),
+        ]
+    }
+    fn initialize(
+        self,
+        context: Moonbase,
+    ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
+        context.set_runtime(self.clone());
+        context.set_resource(self);
+        async move { Ok(()) }
+    }
+}
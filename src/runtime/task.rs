@@ -0,0 +1,119 @@
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    channel::oneshot,
+    future::{AbortHandle, Abortable},
+    FutureExt,
+};
+
+use crate::utils::panic_message;
+
+/// Why a spawned task didn't finish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinError {
+    /// the task is aborted, or dropped by the runtime
+    Aborted,
+    /// the task panicked, with the panic message
+    Panicked(String),
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JoinError::Aborted => write!(f, "task aborted"),
+            JoinError::Panicked(message) => write!(f, "task panicked: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+/// The deadline of [`Runtime::timeout`](super::Runtime::timeout) has elapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl std::fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+/// Resolves with the output of a spawned task, dropping it detaches the task.
+#[derive(Debug)]
+pub struct JoinHandle<T> {
+    result: oneshot::Receiver<Result<T, JoinError>>,
+    abort: Option<AbortHandle>,
+}
+
+impl<T: Send + 'static> JoinHandle<T> {
+    /// Wrap `future` into a task to spawn, and the handle joining it.
+    pub fn task<F>(future: F) -> (impl Future<Output = ()> + Send + 'static, Self)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let (abort, registration) = AbortHandle::new_pair();
+        let task = Abortable::new(AssertUnwindSafe(future).catch_unwind(), registration).map(
+            move |result| {
+                let result = match result {
+                    Ok(Ok(output)) => Ok(output),
+                    Ok(Err(payload)) => Err(JoinError::Panicked(panic_message(payload))),
+                    Err(_) => Err(JoinError::Aborted),
+                };
+                let _ = tx.send(result);
+            },
+        );
+        let handle = JoinHandle {
+            result: rx,
+            abort: Some(abort),
+        };
+        (task, handle)
+    }
+    /// Wrap blocking `job` to run on a thread, and the handle joining it.
+    ///
+    /// A blocking job can't be aborted once it's started.
+    pub fn blocking<F>(job: F) -> (impl FnOnce() + Send + 'static, Self)
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = move || {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(job))
+                .map_err(|payload| JoinError::Panicked(panic_message(payload)));
+            let _ = tx.send(result);
+        };
+        let handle = JoinHandle {
+            result: rx,
+            abort: None,
+        };
+        (job, handle)
+    }
+}
+
+impl<T> JoinHandle<T> {
+    /// Abort the task, it resolves with [`JoinError::Aborted`] unless it's already finished.
+    pub fn abort(&self) {
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+    pub fn abort_handle(&self) -> Option<AbortHandle> {
+        self.abort.clone()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.result
+            .poll_unpin(cx)
+            .map(|result| result.unwrap_or(Err(JoinError::Aborted)))
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use futures::{
+    future::BoxFuture,
+    task::{waker, waker_ref, ArcWake},
+};
+
+use crate::{
+    module::{Dependency, Module},
+    Moonbase,
+};
+
+/// A single threaded runtime for tests, with a virtual clock.
+///
+/// Spawned tasks only run inside [`block_on`](TestRuntime::block_on),
+/// [`run_until_stalled`](TestRuntime::run_until_stalled) or [`advance`](TestRuntime::advance),
+/// and sleeps only elapse when the clock is advanced, so timing is exact and no real time
+/// is spent waiting.
+#[derive(Debug, Clone)]
+pub struct TestRuntime {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    started_at: Instant,
+    elapsed: Mutex<Duration>,
+    ready: Mutex<VecDeque<Arc<Task>>>,
+    /// pending sleeps by id, in the order they are created
+    timers: Mutex<BTreeMap<u64, Timer>>,
+    next_timer: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Timer {
+    deadline: Duration,
+    waker: Waker,
+}
+
+struct Task {
+    future: Mutex<Option<BoxFuture<'static, ()>>>,
+    runtime: Weak<Inner>,
+}
+
+impl std::fmt::Debug for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Task").finish_non_exhaustive()
+    }
+}
+
+impl ArcWake for Task {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if let Some(runtime) = arc_self.runtime.upgrade() {
+            runtime.ready.lock().unwrap().push_back(arc_self.clone());
+        }
+    }
+}
+
+struct Flag(AtomicBool);
+
+impl ArcWake for Flag {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Default for TestRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestRuntime {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                started_at: Instant::now(),
+                elapsed: Mutex::new(Duration::ZERO),
+                ready: Mutex::new(VecDeque::new()),
+                timers: Mutex::new(BTreeMap::new()),
+                next_timer: AtomicU64::new(0),
+            }),
+        }
+    }
+    /// Virtual time passed since the runtime is created.
+    pub fn elapsed(&self) -> Duration {
+        *self.inner.elapsed.lock().unwrap()
+    }
+    /// The virtual current time.
+    pub fn now(&self) -> Instant {
+        self.inner.started_at + self.elapsed()
+    }
+    /// Poll spawned tasks until none of them can make progress without advancing the clock.
+    ///
+    /// Returns whether any task is polled.
+    pub fn run_until_stalled(&self) -> bool {
+        let mut polled = false;
+        loop {
+            let task = self.inner.ready.lock().unwrap().pop_front();
+            let Some(task) = task else {
+                return polled;
+            };
+            polled = true;
+            let mut future = task.future.lock().unwrap();
+            if let Some(running) = future.as_mut() {
+                let waker = waker_ref(&task);
+                let mut cx = Context::from_waker(&waker);
+                if running.as_mut().poll(&mut cx).is_ready() {
+                    *future = None;
+                }
+            }
+        }
+    }
+    /// Advance the clock by `duration`, firing timers in order and running the tasks they wake.
+    pub fn advance(&self, duration: Duration) {
+        let target = self.elapsed() + duration;
+        loop {
+            self.run_until_stalled();
+            match self.next_deadline() {
+                Some(deadline) if deadline <= target => self.advance_to(deadline),
+                _ => break,
+            }
+        }
+        self.advance_to(target);
+        self.run_until_stalled();
+    }
+    /// Run `future` to completion along with spawned tasks.
+    ///
+    /// Whenever everything is waiting, the clock jumps to the next timer.
+    ///
+    /// # Panics
+    /// If everything is waiting while no timer is set, which never resolves.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = pin!(future);
+        let woken = Arc::new(Flag(AtomicBool::new(true)));
+        let waker = waker(woken.clone());
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if woken.0.swap(false, Ordering::SeqCst) {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+            if self.run_until_stalled() || woken.0.load(Ordering::SeqCst) {
+                continue;
+            }
+            let deadline = self
+                .next_deadline()
+                .expect("test runtime stalled, every task is waiting without a timer");
+            self.advance_to(deadline);
+        }
+    }
+    fn next_deadline(&self) -> Option<Duration> {
+        let timers = self.inner.timers.lock().unwrap();
+        timers.values().map(|timer| timer.deadline).min()
+    }
+    fn advance_to(&self, deadline: Duration) {
+        {
+            let mut elapsed = self.inner.elapsed.lock().unwrap();
+            if deadline <= *elapsed {
+                return;
+            }
+            *elapsed = deadline;
+        }
+        let fired = {
+            let mut timers = self.inner.timers.lock().unwrap();
+            let mut fired = Vec::new();
+            timers.retain(|_, timer| {
+                let pending = timer.deadline > deadline;
+                if !pending {
+                    fired.push(timer.waker.clone());
+                }
+                pending
+            });
+            fired
+        };
+        for waker in fired {
+            waker.wake();
+        }
+    }
+}
+
+impl super::Runtime for TestRuntime {
+    fn spawn_detached<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(future))),
+            runtime: Arc::downgrade(&self.inner),
+        });
+        self.inner.ready.lock().unwrap().push_back(task);
+    }
+
+    /// Blocking jobs run in place, to keep the order deterministic.
+    fn spawn_blocking<F, T>(&self, job: F) -> super::JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = super::JoinHandle::blocking(job);
+        job();
+        handle
+    }
+
+    fn now(&self) -> Instant {
+        TestRuntime::now(self)
+    }
+
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        Sleep {
+            id: self.inner.next_timer.fetch_add(1, Ordering::Relaxed),
+            deadline: self.elapsed() + duration,
+            runtime: self.inner.clone(),
+        }
+    }
+}
+
+/// Keeps a single timer while pending, removed once elapsed or dropped.
+struct Sleep {
+    id: u64,
+    deadline: Duration,
+    runtime: Arc<Inner>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut timers = self.runtime.timers.lock().unwrap();
+        if *self.runtime.elapsed.lock().unwrap() >= self.deadline {
+            timers.remove(&self.id);
+            return Poll::Ready(());
+        }
+        timers.insert(
+            self.id,
+            Timer {
+                deadline: self.deadline,
+                waker: cx.waker().clone(),
+            },
+        );
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.runtime.timers.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl Module<Moonbase> for TestRuntime {
+    fn provides() -> Vec<Dependency> {
+        vec![
+            Dependency::resource::<TestRuntime>(),
+            Dependency::resource::<super::RuntimeHandle>(),
+        ]
+    }
+    fn initialize(self, context: Moonbase) -> impl Future<Output = anyhow::Result<()>> + Send {
+        context.set_runtime(self.clone());
+        context.set_resource(self);
+        async move { Ok(()) }
+    }
+}
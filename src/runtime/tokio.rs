@@ -1,4 +1,12 @@
-use crate::{module::Module, resource::MoonbaseResource, Moonbase};
+use futures::FutureExt;
+
+use crate::{
+    module::{Dependency, Module},
+    runtime::RuntimeHandle,
+    shutdown::ShutdownReport,
+    signal::SignalKey,
+    Moonbase,
+};
 
 #[derive(Debug, Clone)]
 pub struct Tokio {
@@ -14,24 +22,94 @@ impl Default for Tokio {
 }
 
 impl super::Runtime for Tokio {
-    fn spawn<F>(&self, future: F)
+    fn spawn_detached<F>(&self, future: F)
     where
         F: std::future::Future<Output = ()> + Send + 'static,
     {
         self.inner.spawn(future);
     }
 
+    fn spawn_blocking<F, T>(&self, job: F) -> super::JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = super::JoinHandle::blocking(job);
+        self.inner.spawn_blocking(job);
+        handle
+    }
+
     fn sleep(&self, duration: std::time::Duration) -> impl std::future::Future<Output = ()> + Send {
         tokio::time::sleep(duration)
     }
 }
 
 impl Module<Moonbase> for Tokio {
+    fn provides() -> Vec<Dependency> {
+        vec![
+            Dependency::resource::<Tokio>(),
+            Dependency::resource::<RuntimeHandle>(),
+        ]
+    }
     fn initialize(
         self,
         context: Moonbase,
     ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
+        context.set_runtime(self.clone());
         context.set_resource(self);
         async move { Ok(()) }
     }
 }
+
+/// Resolves when the process receives SIGINT, SIGTERM or SIGHUP (or ctrl-c outside unix).
+pub async fn terminate_signal() -> std::io::Result<()> {
+    listen_terminate_signal()?.await
+}
+
+/// Listen to the signals of [`terminate_signal`] right away rather than on the first poll.
+fn listen_terminate_signal(
+) -> std::io::Result<impl std::future::Future<Output = std::io::Result<()>> + Send> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut hangup = signal(SignalKind::hangup())?;
+        Ok(async move {
+            futures::select! {
+                _ = interrupt.recv().fuse() => {}
+                _ = terminate.recv().fuse() => {}
+                _ = hangup.recv().fuse() => {}
+            }
+            Ok(())
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(tokio::signal::ctrl_c())
+    }
+}
+
+impl Moonbase {
+    /// Run until the process is asked to terminate, then shut the moonbase down.
+    ///
+    /// Either an OS signal (see [`terminate_signal`]) or triggering [`SignalKey::SHUTDOWN`]
+    /// starts the [shutdown](Moonbase::shutdown), daemons are given `timeout` to stop. Both are
+    /// listened to when this is called, so a signal arriving before the future is polled isn't
+    /// missed.
+    pub fn run_until_terminated(
+        &self,
+        timeout: std::time::Duration,
+    ) -> impl std::future::Future<Output = std::io::Result<ShutdownReport>> + Send + '_ {
+        let shutdown_requested = self.get_or_insert_signal(SignalKey::SHUTDOWN).recv();
+        let terminated = listen_terminate_signal();
+        async move {
+            let terminated = terminated?;
+            futures::select! {
+                terminated = terminated.fuse() => terminated?,
+                _ = shutdown_requested.fuse() => {}
+            }
+            Ok(self.shutdown(timeout).await)
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use crate::{signal::SignalKey, Moonbase};
+
+/// What went wrong during [`Moonbase::shutdown`].
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    /// names of daemons which were still running when the deadline elapsed
+    pub timed_out_daemons: Vec<String>,
+    /// modules whose [`finalize`](crate::module::Module::finalize) returned an error
+    pub failed_modules: Vec<(&'static str, anyhow::Error)>,
+    /// names of tasks which were still outstanding and got cancelled, not counted as errors
+    pub cancelled_tasks: Vec<String>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.timed_out_daemons.is_empty() && self.failed_modules.is_empty()
+    }
+}
+
+impl std::fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_clean() {
+            return write!(f, "shutdown completed");
+        }
+        write!(f, "shutdown completed with errors:")?;
+        for daemon in &self.timed_out_daemons {
+            write!(f, "\n  daemon `{daemon}` failed to stop in time")?;
+        }
+        for (module, error) in &self.failed_modules {
+            write!(f, "\n  module `{module}` failed to finalize: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Moonbase {
+    /// Stop the moonbase gracefully.
+    ///
+    /// [`SignalKey::SHUTDOWN`] is triggered, all daemons are killed and given `timeout` to stop,
+    /// outstanding tasks are cancelled, then the modules are finalized in reverse load order.
+    ///
+    /// A [child](Moonbase::child) only stops what's its own, the daemons run and the tasks
+    /// spawned in it, while its parent and the shared modules are left running.
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        let root = self.is_root();
+        if root {
+            self.trigger_signal(&SignalKey::SHUTDOWN);
+        }
+        let timed_out_daemons = self
+            .kill_all_daemons(timeout)
+            .await
+            .iter()
+            .map(|handle| handle.name().to_owned())
+            .collect();
+        let cancelled_tasks = self
+            .cancel_all_tasks()
+            .into_iter()
+            .map(|task| task.name.to_string())
+            .collect();
+        let failed_modules = if root {
+            self.finalize_modules().await
+        } else {
+            Vec::new()
+        };
+        ShutdownReport {
+            timed_out_daemons,
+            failed_modules,
+            cancelled_tasks,
+        }
+    }
+}
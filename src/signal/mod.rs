@@ -15,6 +15,8 @@ pub struct SignalKey {
 }
 
 impl SignalKey {
+    /// Triggered when the moonbase is requested to shut down.
+    pub const SHUTDOWN: SignalKey = SignalKey::from_static_str("moonbase::shutdown");
     pub fn symbol<T: Any>() -> Self {
         Self {
             id: Cow::Owned(
@@ -183,21 +185,28 @@ impl Moonbase {
         let mut signals = self.signals.write().unwrap();
         signals.insert(key, signal);
     }
+    /// Falls back to the parent context if it's not set here.
     pub fn get_signal(&self, key: &SignalKey) -> Option<Signal> {
-        let signals = self.signals.read().unwrap();
-        signals.get(key).cloned()
+        let signal = self.signals.read().unwrap().get(key).cloned();
+        signal.or_else(|| self.parent()?.get_signal(key))
+    }
+    /// Inserted into this context if neither it nor its parents have the signal.
+    pub fn get_or_insert_signal(&self, key: SignalKey) -> Signal {
+        if let Some(signal) = self.get_signal(&key) {
+            return signal;
+        }
+        let mut signals = self.signals.write().unwrap();
+        signals.entry(key).or_default().clone()
     }
     pub fn remove_signal(&self, key: &SignalKey) -> Option<Signal> {
         let mut signals = self.signals.write().unwrap();
         signals.remove(key)
     }
     pub fn has_signal(&self, key: &SignalKey) -> bool {
-        let signals = self.signals.read().unwrap();
-        signals.contains_key(key)
+        self.get_signal(key).is_some()
     }
     pub fn trigger_signal(&self, key: &SignalKey) {
-        let signals = self.signals.read().unwrap();
-        if let Some(signal) = signals.get(key) {
+        if let Some(signal) = self.get_signal(key) {
             signal.get_sender().send();
         }
     }
@@ -0,0 +1,286 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use crossbeam::atomic::AtomicCell;
+use futures::{future::AbortHandle, FutureExt};
+
+use crate::{
+    daemon::DaemonInfo,
+    runtime::{JoinError, JoinHandle, Runtime, RuntimeNotSet},
+    Moonbase,
+};
+
+/// How many ended tasks are kept for [`Moonbase::tasks`], the oldest are dropped first.
+pub const ENDED_TASKS_KEPT: usize = 32;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TaskStatus {
+    Running,
+    Finished,
+    Cancelled,
+    Panicked,
+}
+
+/// A snapshot of a task, see [`Moonbase::tasks`].
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub id: u64,
+    pub name: Arc<str>,
+    /// by the runtime's clock
+    pub spawned_at: Instant,
+    pub status: TaskStatus,
+}
+
+/// A daemon or a task, see [`Moonbase::workloads`].
+#[derive(Debug, Clone)]
+pub enum WorkloadInfo {
+    Daemon(DaemonInfo),
+    Task(TaskInfo),
+}
+
+impl WorkloadInfo {
+    pub fn name(&self) -> &str {
+        match self {
+            WorkloadInfo::Daemon(daemon) => &daemon.name,
+            WorkloadInfo::Task(task) => &task.name,
+        }
+    }
+    /// Whether the daemon or the task hasn't ended yet.
+    pub fn is_running(&self) -> bool {
+        match self {
+            WorkloadInfo::Daemon(daemon) => !daemon.status.is_terminated(),
+            WorkloadInfo::Task(task) => task.status == TaskStatus::Running,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TaskEntry {
+    info: TaskInfo,
+    /// id of the context spawning it
+    context: u64,
+    status: Arc<AtomicCell<TaskStatus>>,
+    abort: Option<AbortHandle>,
+}
+
+impl TaskEntry {
+    fn info(&self) -> TaskInfo {
+        TaskInfo {
+            status: self.status.load(),
+            ..self.info.clone()
+        }
+    }
+    fn cancel(&self) -> bool {
+        let cancelled = self
+            .status
+            .compare_exchange(TaskStatus::Running, TaskStatus::Cancelled)
+            .is_ok();
+        if cancelled {
+            if let Some(abort) = &self.abort {
+                abort.abort();
+            }
+        }
+        cancelled
+    }
+}
+
+#[derive(Debug, Default)]
+struct TaskRegistryInner {
+    next_id: u64,
+    tasks: HashMap<u64, TaskEntry>,
+    /// the last ended tasks, oldest first
+    ended: VecDeque<TaskInfo>,
+}
+
+/// Tasks of a moonbase, tasks are moved to the ended ones once they end.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskRegistry {
+    inner: Arc<Mutex<TaskRegistryInner>>,
+}
+
+impl TaskRegistry {
+    fn remove(&self, id: u64) -> Option<TaskEntry> {
+        self.inner.lock().unwrap().tasks.remove(&id)
+    }
+    /// Keep a removed task as ended, see [`ENDED_TASKS_KEPT`].
+    fn ended(&self, entry: &TaskEntry) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ended.push_back(entry.info());
+        if inner.ended.len() > ENDED_TASKS_KEPT {
+            inner.ended.pop_front();
+        }
+    }
+    /// Remove and cancel a task, returns false if it's already ended.
+    fn cancel(&self, id: u64) -> bool {
+        let Some(entry) = self.remove(id) else {
+            return false;
+        };
+        let cancelled = entry.cancel();
+        self.ended(&entry);
+        cancelled
+    }
+}
+
+/// Handle of a task spawned by [`Moonbase::spawn_task`], it resolves with the task output.
+///
+/// Dropping the handle detaches the task, it's still tracked until it ends.
+#[derive(Debug)]
+pub struct TaskHandle<T> {
+    info: TaskInfo,
+    status: Arc<AtomicCell<TaskStatus>>,
+    join: JoinHandle<T>,
+    registry: TaskRegistry,
+}
+
+impl<T> TaskHandle<T> {
+    pub fn id(&self) -> u64 {
+        self.info.id
+    }
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+    pub fn status(&self) -> TaskStatus {
+        self.status.load()
+    }
+    /// Cancel the task, returns false if it's already ended.
+    pub fn cancel(&self) -> bool {
+        self.registry.cancel(self.info.id)
+    }
+}
+
+impl<T> Future for TaskHandle<T> {
+    type Output = Result<T, JoinError>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.join.poll_unpin(cx)
+    }
+}
+
+impl Moonbase {
+    /// Spawn a one-shot task on the runtime, tracked until it ends.
+    ///
+    /// Tasks are listed by [`Moonbase::tasks`] along with their status, outstanding ones are
+    /// cancelled on [shutdown](Moonbase::shutdown).
+    pub fn spawn_task<F>(
+        &self,
+        name: impl Into<Arc<str>>,
+        future: F,
+    ) -> Result<TaskHandle<F::Output>, RuntimeNotSet>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let runtime = self.runtime()?;
+        let status = Arc::new(AtomicCell::new(TaskStatus::Running));
+        let registry = self.tasks.clone();
+        let mut inner = registry.inner.lock().unwrap();
+        let info = TaskInfo {
+            id: inner.next_id,
+            name: name.into(),
+            spawned_at: runtime.now(),
+            status: TaskStatus::Running,
+        };
+        inner.next_id += 1;
+        let task = {
+            let status = status.clone();
+            let registry = registry.clone();
+            let id = info.id;
+            async move {
+                let result = AssertUnwindSafe(future).catch_unwind().await;
+                let ended = match result {
+                    Ok(_) => TaskStatus::Finished,
+                    Err(_) => TaskStatus::Panicked,
+                };
+                // a cancelled task may still end before it's aborted
+                let _ = status.compare_exchange(TaskStatus::Running, ended);
+                if let Some(entry) = registry.remove(id) {
+                    registry.ended(&entry);
+                }
+                match result {
+                    Ok(output) => output,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                }
+            }
+        };
+        // registered before spawned, so a task ending at once is removed as well
+        let (task, join) = JoinHandle::task(task);
+        inner.tasks.insert(
+            info.id,
+            TaskEntry {
+                info: info.clone(),
+                context: self.id(),
+                status: status.clone(),
+                abort: join.abort_handle(),
+            },
+        );
+        drop(inner);
+        runtime.spawn_detached(task);
+        Ok(TaskHandle {
+            info,
+            status,
+            join,
+            registry,
+        })
+    }
+    /// Outstanding tasks and the last [`ENDED_TASKS_KEPT`] ended ones, sorted by spawn order.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let inner = self.tasks.inner.lock().unwrap();
+        let mut tasks = inner
+            .tasks
+            .values()
+            .map(TaskEntry::info)
+            .chain(inner.ended.iter().cloned())
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(|task| task.id);
+        tasks
+    }
+    /// Snapshots of every daemon and task, daemons first as listed by [`Moonbase::daemons`],
+    /// then tasks as listed by [`Moonbase::tasks`].
+    pub fn workloads(&self) -> Vec<WorkloadInfo> {
+        self.daemons()
+            .into_iter()
+            .map(WorkloadInfo::Daemon)
+            .chain(self.tasks().into_iter().map(WorkloadInfo::Task))
+            .collect()
+    }
+    /// Cancel a task by id, returns false if it's already ended.
+    pub fn cancel_task(&self, id: u64) -> bool {
+        self.tasks.cancel(id)
+    }
+    /// Cancel every outstanding task, returns the cancelled ones.
+    ///
+    /// A child context only cancels the tasks it spawned itself.
+    pub fn cancel_all_tasks(&self) -> Vec<TaskInfo> {
+        let entries = {
+            let mut inner = self.tasks.inner.lock().unwrap();
+            if self.is_root() {
+                std::mem::take(&mut inner.tasks)
+            } else {
+                let ids = inner
+                    .tasks
+                    .iter()
+                    .filter(|(_, entry)| entry.context == self.id())
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<_>>();
+                ids.iter()
+                    .filter_map(|id| inner.tasks.remove_entry(id))
+                    .collect()
+            }
+        };
+        let mut cancelled = Vec::new();
+        for entry in entries.into_values() {
+            if entry.cancel() {
+                cancelled.push(entry.info());
+            }
+            self.tasks.ended(&entry);
+        }
+        cancelled.sort_by_key(|task| task.id);
+        cancelled
+    }
+}
@@ -2,4 +2,6 @@ mod anymap;
 pub use anymap::AnyMap;
 mod hash;
 pub use hash::hash;
+mod panic;
+pub use panic::panic_message;
 mod tuples_marco;
\ No newline at end of file
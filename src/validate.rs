@@ -0,0 +1,158 @@
+use std::{any::TypeId, collections::HashSet, future::Future};
+
+use crate::{
+    daemon::Daemon,
+    extract::{TryExtractFrom, TryExtractTuple},
+    handler::{Call, Handler},
+    module::{Dependency, Module},
+    Moonbase,
+};
+
+/// What declares a requirement checked by a [`Validation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Requirer {
+    Handler(&'static str),
+    Daemon(&'static str),
+    Module(&'static str),
+}
+
+impl std::fmt::Display for Requirer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Requirer::Handler(name) => write!(f, "handler `{name}`"),
+            Requirer::Daemon(name) => write!(f, "daemon `{name}`"),
+            Requirer::Module(name) => write!(f, "module `{name}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MissingRequirement {
+    pub required_by: Requirer,
+    pub dependency: Dependency,
+}
+
+/// Requirements not satisfied by the moonbase, see [`Validation::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub missing: Vec<MissingRequirement>,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "missing requirements:")?;
+        for MissingRequirement {
+            required_by,
+            dependency,
+        } in &self.missing
+        {
+            write!(f, "\n  {required_by} requires {dependency}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check at boot that handlers, daemons and modules will find what they extract.
+///
+/// Requirements come from [`TryExtractFrom::requirements`], which every
+/// [`ExtractFrom`](crate::extract::ExtractFrom) forwards, and [`Module::dependencies`]. An
+/// `Option` or a `Result` of an extractor requires nothing, as it handles the absence. A
+/// resource is present if it or its factory is set, or if a module added to the validation
+/// [provides](Module::provides) it.
+///
+/// ```ignore
+/// moonbase
+///     .validate()
+///     .handler(&create_user)
+///     .daemon::<Mailer>()
+///     .check()?;
+/// ```
+#[derive(Debug)]
+pub struct Validation {
+    moonbase: Moonbase,
+    requirements: Vec<MissingRequirement>,
+    modules: HashSet<TypeId>,
+    provided: HashSet<TypeId>,
+}
+
+impl Validation {
+    pub fn new(moonbase: &Moonbase) -> Self {
+        Self {
+            moonbase: moonbase.clone(),
+            requirements: Vec::new(),
+            modules: HashSet::new(),
+            provided: HashSet::new(),
+        }
+    }
+    fn require(mut self, required_by: Requirer, dependencies: Vec<Dependency>) -> Self {
+        for dependency in dependencies {
+            let requirement = MissingRequirement {
+                required_by,
+                dependency,
+            };
+            if !self.requirements.contains(&requirement) {
+                self.requirements.push(requirement);
+            }
+        }
+        self
+    }
+    /// Require what the arguments of a handler called by
+    /// [`call`](crate::context::ContextExt::call) or [`try_call`](crate::context::ContextExt::try_call) extract.
+    pub fn handler<T, R, H>(self, _handler: &H) -> Self
+    where
+        H: Handler<Call<T, R>>,
+        T: TryExtractTuple<Moonbase>,
+        R: Future,
+    {
+        let required_by = Requirer::Handler(std::any::type_name::<H>());
+        self.require(required_by, T::requirements())
+    }
+    /// Require what a daemon extracts when it's started, as its
+    /// [`TryExtractFrom::requirements`] lists.
+    pub fn daemon<D: Daemon<Moonbase>>(self) -> Self {
+        let required_by = Requirer::Daemon(std::any::type_name::<D>());
+        self.require(required_by, <D as TryExtractFrom<Moonbase>>::requirements())
+    }
+    /// Require the dependencies of a module, and count what it provides as present.
+    pub fn module<M: Module<Moonbase>>(mut self) -> Self {
+        self.modules.insert(TypeId::of::<M>());
+        self.provided
+            .extend(M::provides().iter().map(Dependency::type_id));
+        self.require(Requirer::Module(M::module_name()), M::dependencies())
+    }
+    /// Every requirement not satisfied, in the order they are added.
+    pub fn missing(&self) -> Vec<MissingRequirement> {
+        self.requirements
+            .iter()
+            .filter(|requirement| !self.is_satisfied(&requirement.dependency))
+            .copied()
+            .collect()
+    }
+    fn is_satisfied(&self, dependency: &Dependency) -> bool {
+        match dependency {
+            Dependency::Module { type_id, .. } => {
+                self.modules.contains(type_id) || self.moonbase.is_module_loaded(type_id)
+            }
+            Dependency::Resource { type_id, .. } => {
+                self.provided.contains(type_id) || self.moonbase.has_resource_type_id(type_id)
+            }
+        }
+    }
+    pub fn check(&self) -> Result<(), ValidationError> {
+        let missing = self.missing();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { missing })
+        }
+    }
+}
+
+impl Moonbase {
+    /// Start a [`Validation`] of what's required from this moonbase.
+    pub fn validate(&self) -> Validation {
+        Validation::new(self)
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{body::Body, extract::Request, http::StatusCode, routing::get};
+use moonbase::{
+    extension::axum::{request_context, Authenticator, Principal, RequestId, Scoped},
+    resource::Resource,
+    Moonbase,
+};
+use tower::Service;
+
+#[derive(Debug, Clone)]
+struct Tenant;
+
+async fn whoami(
+    Scoped((request_id, principal, moonbase)): Scoped<(RequestId, Option<Principal>, Moonbase)>,
+) -> String {
+    // resources set on the request scoped moonbase don't leak to the app
+    moonbase.set_resource(Tenant);
+    let name = principal.map(|principal| principal.id).unwrap_or_default();
+    format!("{request_id} {name}")
+}
+
+#[derive(Debug, Clone)]
+struct Missing;
+
+async fn missing(Resource(_): Resource<Missing>) {}
+
+async fn scoped_missing(Scoped(Resource(_)): Scoped<Resource<Missing>>) {}
+
+async fn admin(principal: Principal) -> String {
+    principal.has_role("admin").to_string()
+}
+
+#[tokio::test]
+async fn test_request_context() {
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(Authenticator::new(|_, headers| async move {
+        let id = headers.get("authorization")?.to_str().ok()?;
+        Some(Principal::new(id).with_role("admin"))
+    }));
+    let mut router = axum::Router::new()
+        .route("/", get(whoami))
+        .route("/admin", get(admin))
+        .layer(axum::middleware::from_fn_with_state(
+            moonbase.clone(),
+            request_context,
+        ))
+        .with_state(moonbase.clone());
+
+    let request = Request::get("/")
+        .header("x-request-id", "abc")
+        .header("authorization", "alice")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.headers()["x-request-id"], "abc");
+    let body = axum::body::to_bytes(response.into_body(), 1024)
+        .await
+        .unwrap();
+    assert_eq!(body, "abc alice");
+    assert!(!moonbase.has_resource::<Tenant>());
+
+    let request = Request::get("/").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    assert!(response.headers().contains_key("x-request-id"));
+
+    let request = Request::get("/admin").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_resource_rejection() {
+    let moonbase = Moonbase::new();
+    let mut router = axum::Router::new()
+        .route("/missing", get(missing))
+        .with_state(moonbase);
+    let request = Request::get("/missing").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = axum::body::to_bytes(response.into_body(), 1024)
+        .await
+        .unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("Missing` not found"), "{body}");
+}
+
+#[tokio::test]
+async fn test_scoped_rejection() {
+    let moonbase = Moonbase::new();
+    let mut router = axum::Router::new()
+        .route("/missing", get(scoped_missing))
+        .layer(axum::middleware::from_fn_with_state(
+            moonbase.clone(),
+            request_context,
+        ))
+        .with_state(moonbase);
+    let request = Request::get("/missing").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = axum::body::to_bytes(response.into_body(), 1024)
+        .await
+        .unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("Missing` not found"), "{body}");
+}
@@ -0,0 +1,101 @@
+use moonbase::{
+    components::{ComponentName, MoonbaseComponent},
+    context::{Context, ContextExt},
+    extract::TryExtractFrom,
+    module::Dependency,
+    resource::Resource,
+    signal::SignalKey,
+    Moonbase,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Tenant(&'static str);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Config(u32);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Limit(u32);
+
+impl MoonbaseComponent for Limit {}
+
+#[test]
+fn test_child_context() {
+    let root = Moonbase::new();
+    root.set_resource(Config(1));
+    let limit = ComponentName::<Limit>::new("limit");
+    root.set_component(&limit, Limit(100));
+
+    let child = root.child();
+    assert!(!child.is_root());
+    assert_ne!(child.id(), root.id());
+    assert_eq!(child.parent().unwrap().id(), root.id());
+    let grandchild = child.child();
+    assert_eq!(grandchild.root().id(), root.id());
+
+    // reads fall back to the parent
+    assert_eq!(grandchild.get_resource::<Config>(), Some(Config(1)));
+    assert_eq!(grandchild.get_component(&limit), Some(Limit(100)));
+
+    // writes stay local
+    child.set_resource(Tenant("acme"));
+    child.set_resource(Config(2));
+    assert_eq!(grandchild.get_resource::<Config>(), Some(Config(2)));
+    assert_eq!(grandchild.get_resource::<Tenant>(), Some(Tenant("acme")));
+    assert_eq!(root.get_resource::<Config>(), Some(Config(1)));
+    assert!(!root.has_resource::<Tenant>());
+
+    assert_eq!(child.remove_resource::<Config>(), Some(Config(2)));
+    assert_eq!(child.remove_resource::<Config>(), None);
+    assert_eq!(child.get_resource::<Config>(), Some(Config(1)));
+
+    // signals of the parent are shared, new ones stay local
+    let key = SignalKey::from_static_str("reload");
+    let signal = root.get_or_insert_signal(key.clone());
+    let waiting = signal.recv();
+    child.trigger_signal(&key);
+    futures::executor::block_on(waiting);
+    let local = SignalKey::from_static_str("local");
+    child.get_or_insert_signal(local.clone());
+    assert!(grandchild.has_signal(&local));
+    assert!(!root.has_signal(&local));
+}
+
+async fn describe(Resource(config): Resource<Config>, context: Moonbase) -> String {
+    format!("config {} in #{}", config.0, context.id())
+}
+
+#[test]
+fn test_try_call() {
+    let moonbase = Moonbase::new();
+    // infallible extractors are fallible ones never failing
+    let context = futures::executor::block_on(moonbase.try_extract::<Moonbase>());
+    assert_eq!(context.unwrap().id(), moonbase.id());
+    assert_eq!(
+        <Option<Resource<Config>> as TryExtractFrom<Moonbase>>::requirements(),
+        []
+    );
+
+    let error = futures::executor::block_on(moonbase.try_call(describe)).unwrap_err();
+    assert!(error.to_string().contains("Resource<"), "{error}");
+    assert!(format!("{error:#}").contains("not found"), "{error:#}");
+
+    // a tuple mixing fallible extractors
+    let error =
+        futures::executor::block_on(moonbase.try_extract_tuple::<(Resource<Config>, Moonbase)>())
+            .unwrap_err();
+    assert!(error.to_string().contains("Resource<"), "{error}");
+
+    moonbase.set_resource(Config(7));
+    let (Resource(config), _) =
+        futures::executor::block_on(moonbase.try_extract_tuple::<(Resource<Config>, Moonbase)>())
+            .unwrap();
+    assert_eq!(config, Config(7));
+    let described = futures::executor::block_on(moonbase.try_call(describe)).unwrap();
+    assert_eq!(described, "config 7 in #0");
+    assert!(moonbase.validate().handler(&describe).check().is_ok());
+    assert_eq!(
+        Moonbase::new().validate().handler(&describe).missing()[0].dependency,
+        Dependency::resource::<Config>()
+    );
+}
@@ -0,0 +1,696 @@
+use std::{
+    convert::Infallible,
+    future::IntoFuture,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::{Future, StreamExt};
+use moonbase::{
+    daemon::{
+        Backoff, Daemon, DaemonEventKind, DaemonHandle, DaemonOutcome, DaemonStatus, GroupStrategy,
+        RestartPolicy,
+    },
+    extract::TryExtractFrom,
+    module::Dependency,
+    runtime::{Runtime, RuntimeHandle, RuntimeNotSet, TestRuntime, Tokio},
+    task::WorkloadInfo,
+    Moonbase,
+};
+
+#[test]
+fn test_backoff() {
+    let backoff = Backoff::exponential(Duration::from_millis(100), Duration::from_secs(1));
+    assert_eq!(backoff.delay(0), Duration::from_millis(100));
+    assert_eq!(backoff.delay(2), Duration::from_millis(400));
+    assert_eq!(backoff.delay(10), Duration::from_secs(1));
+    let jittered = backoff.with_jitter(0.5);
+    for attempt in 0..8 {
+        let delay = jittered.delay(attempt);
+        let expected = backoff.delay(attempt);
+        assert!(delay >= expected / 2 && delay <= expected * 3 / 2);
+        // capped after the jitter
+        assert!(delay <= Duration::from_secs(1));
+    }
+    // overflowing delays are capped rather than panicking
+    let unbounded = Backoff::exponential(Duration::from_secs(1), Duration::MAX);
+    assert_eq!(unbounded.delay(u32::MAX), Duration::MAX);
+}
+
+/// exits shortly after started
+#[derive(Debug)]
+struct Flaky {
+    runtime: RuntimeHandle,
+}
+
+impl TryExtractFrom<Moonbase> for Flaky {
+    type Error = RuntimeNotSet;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Flaky {
+            runtime: context.runtime()?,
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<RuntimeHandle>()]
+    }
+}
+
+impl IntoFuture for Flaky {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            self.runtime.sleep(Duration::from_millis(20)).await;
+            self
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Flaky {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new()
+            .max_restarts(2)
+            .backoff(Backoff::exponential(
+                Duration::from_millis(10),
+                Duration::from_millis(100),
+            ))
+    }
+}
+
+/// exits right after started
+#[derive(Debug)]
+struct Crashing;
+
+impl TryExtractFrom<Moonbase> for Crashing {
+    type Error = Infallible;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Crashing)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl IntoFuture for Crashing {
+    type Output = Self;
+    type IntoFuture = std::future::Ready<Self>;
+    fn into_future(self) -> Self::IntoFuture {
+        std::future::ready(self)
+    }
+}
+
+impl Daemon<Moonbase> for Crashing {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new().intensity(3, Duration::from_secs(60))
+    }
+}
+
+/// fails on every run, and gives up at the third failure
+#[derive(Debug)]
+struct Unreachable;
+
+impl TryExtractFrom<Moonbase> for Unreachable {
+    type Error = Infallible;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Unreachable)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl IntoFuture for Unreachable {
+    type Output = anyhow::Result<Self>;
+    type IntoFuture = std::future::Ready<Self::Output>;
+    fn into_future(self) -> Self::IntoFuture {
+        std::future::ready(Err(anyhow::anyhow!("connection refused")))
+    }
+}
+
+impl Daemon<Moonbase> for Unreachable {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new().max_restarts(2)
+    }
+}
+
+/// stops or fails for good after its first run
+#[derive(Debug)]
+struct OneShot {
+    fatal: bool,
+}
+
+impl TryExtractFrom<Moonbase> for OneShot {
+    type Error = Infallible;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(OneShot {
+            fatal: context.has_resource::<Fatal>(),
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Fatal;
+
+impl IntoFuture for OneShot {
+    type Output = DaemonOutcome<Self>;
+    type IntoFuture = std::future::Ready<Self::Output>;
+    fn into_future(self) -> Self::IntoFuture {
+        std::future::ready(if self.fatal {
+            DaemonOutcome::fatal(anyhow::anyhow!("invalid config"))
+        } else {
+            DaemonOutcome::Stop
+        })
+    }
+}
+
+impl Daemon<Moonbase> for OneShot {}
+
+/// panics on every run
+#[derive(Debug)]
+struct Panicky;
+
+impl TryExtractFrom<Moonbase> for Panicky {
+    type Error = Infallible;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Panicky)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl IntoFuture for Panicky {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { panic!("boom") })
+    }
+}
+
+impl Daemon<Moonbase> for Panicky {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new().max_restarts(1)
+    }
+}
+
+macro_rules! sleeper {
+    ($name: ident) => {
+        #[derive(Debug)]
+        struct $name {
+            runtime: RuntimeHandle,
+        }
+
+        impl TryExtractFrom<Moonbase> for $name {
+            type Error = RuntimeNotSet;
+            async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+                Ok($name {
+                    runtime: context.runtime()?,
+                })
+            }
+            fn requirements() -> Vec<Dependency> {
+                vec![Dependency::resource::<RuntimeHandle>()]
+            }
+        }
+
+        impl IntoFuture for $name {
+            type Output = Self;
+            type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+            fn into_future(self) -> Self::IntoFuture {
+                Box::pin(async move {
+                    self.runtime.sleep(Duration::from_secs(3600)).await;
+                    self
+                })
+            }
+        }
+
+        impl Daemon<Moonbase> for $name {}
+    };
+}
+
+sleeper!(Database);
+sleeper!(Listener);
+sleeper!(Worker);
+
+/// Run `test` with a moonbase on a [`TestRuntime`], so timing is exact.
+fn run<F, Fut>(test: F)
+where
+    F: FnOnce(Moonbase, TestRuntime) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let runtime = TestRuntime::new();
+    runtime.block_on(async {
+        let moonbase = Moonbase::builder()
+            .module(runtime.clone())
+            .build()
+            .await
+            .unwrap();
+        test(moonbase, runtime.clone()).await
+    });
+}
+
+async fn wait(runtime: &TestRuntime, handle: &DaemonHandle) {
+    runtime
+        .timeout(Duration::from_secs(600), handle.wait())
+        .await
+        .expect("daemon should stop");
+}
+
+#[test]
+fn test_restart_intensity() {
+    run(|moonbase, runtime| async move {
+        let handle = moonbase.run_daemon::<Crashing>().await.unwrap();
+        wait(&runtime, &handle).await;
+        assert_eq!(handle.state(), DaemonStatus::Terminated);
+        assert_eq!(handle.restarted_times(), 3);
+    });
+}
+
+#[test]
+fn test_outcome() {
+    run(|moonbase, runtime| async move {
+        let handle = moonbase.run_daemon::<Unreachable>().await.unwrap();
+        wait(&runtime, &handle).await;
+        assert_eq!(handle.state(), DaemonStatus::Failed);
+        assert_eq!(handle.restarted_times(), 2);
+        let error = handle.last_error().unwrap();
+        assert!(!error.fatal);
+        assert_eq!(error.to_string(), "connection refused");
+
+        let handle = moonbase.run_daemon::<OneShot>().await.unwrap();
+        wait(&runtime, &handle).await;
+        assert_eq!(handle.state(), DaemonStatus::Terminated);
+        assert_eq!(handle.restarted_times(), 0);
+        assert!(handle.last_error().is_none());
+
+        moonbase.set_resource(Fatal);
+        let handle = moonbase.run_daemon::<OneShot>().await.unwrap();
+        wait(&runtime, &handle).await;
+        assert_eq!(handle.state(), DaemonStatus::Failed);
+        assert!(handle.last_error().unwrap().fatal);
+    });
+}
+
+#[test]
+fn test_panic() {
+    run(|moonbase, runtime| async move {
+        let handle = moonbase.run_daemon::<Panicky>().await.unwrap();
+        wait(&runtime, &handle).await;
+        assert_eq!(handle.state(), DaemonStatus::Panicked);
+        assert_eq!(handle.restarted_times(), 1);
+        let error = handle.last_error().unwrap();
+        assert_eq!(error.panic_message(), Some("boom"));
+    });
+}
+
+#[test]
+fn test_backoff_restart() {
+    run(|moonbase, runtime| async move {
+        let handle = moonbase.run_daemon::<Flaky>().await.unwrap();
+        wait(&runtime, &handle).await;
+        assert_eq!(handle.restarted_times(), 2);
+        // 3 runs of 20ms, and backoff of 10ms then 20ms
+        assert_eq!(runtime.elapsed(), Duration::from_millis(90));
+    });
+}
+
+#[test]
+fn test_one_for_all() {
+    run(|moonbase, runtime| async move {
+        let handles = moonbase
+            .daemon_group("database")
+            .strategy(GroupStrategy::OneForAll)
+            .daemon::<Listener>()
+            .daemon::<Flaky>()
+            .daemon::<Worker>()
+            .run()
+            .await
+            .unwrap();
+        wait(&runtime, &handles[1]).await;
+        for handle in &handles {
+            wait(&runtime, handle).await;
+            assert_eq!(handle.state(), DaemonStatus::Terminated);
+            assert_eq!(handle.restarted_times(), 2);
+        }
+    });
+}
+
+#[test]
+fn test_rest_for_one() {
+    run(|moonbase, runtime| async move {
+        let handles = moonbase
+            .daemon_group("database")
+            .strategy(GroupStrategy::RestForOne)
+            .daemon::<Database>()
+            .daemon::<Flaky>()
+            .daemon::<Worker>()
+            .run()
+            .await
+            .unwrap();
+        wait(&runtime, &handles[1]).await;
+        wait(&runtime, &handles[2]).await;
+        assert_eq!(handles[2].restarted_times(), 2);
+        assert_eq!(handles[0].state(), DaemonStatus::Running);
+        assert_eq!(handles[0].restarted_times(), 0);
+    });
+}
+
+#[test]
+fn test_group_duplicated_member() {
+    run(|moonbase, _runtime| async move {
+        let error = moonbase
+            .daemon_group("workers")
+            .daemon::<Worker>()
+            .daemon::<Worker>()
+            .run()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("more than once"));
+        assert!(moonbase.daemons().is_empty());
+    });
+}
+
+/// never extracted
+#[derive(Debug)]
+struct Unstartable;
+
+impl TryExtractFrom<Moonbase> for Unstartable {
+    type Error = RuntimeNotSet;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Err(RuntimeNotSet)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<RuntimeHandle>()]
+    }
+}
+
+impl IntoFuture for Unstartable {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { self })
+    }
+}
+
+impl Daemon<Moonbase> for Unstartable {}
+
+#[test]
+fn test_group_member_fails_to_start() {
+    run(|moonbase, _runtime| async move {
+        let error = moonbase
+            .daemon_group("workers")
+            .daemon::<Worker>()
+            .daemon::<Unstartable>()
+            .run()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("workers"), "{error:#}");
+        let daemons = moonbase.daemons();
+        assert_eq!(daemons.len(), 1);
+        assert!(daemons[0].name.ends_with("Worker"));
+        assert_eq!(daemons[0].status, DaemonStatus::Terminated);
+    });
+}
+
+#[test]
+fn test_daemons() {
+    run(|moonbase, runtime| async move {
+        moonbase.run_daemon::<Listener>().await.unwrap();
+        let handle = moonbase.run_daemon::<Unreachable>().await.unwrap();
+        wait(&runtime, &handle).await;
+        let daemons = moonbase.daemons();
+        assert_eq!(daemons.len(), 2);
+        assert!(daemons[0].name.ends_with("Listener"));
+        assert_eq!(daemons[0].status, DaemonStatus::Running);
+        assert!(daemons[0].uptime.is_some());
+        assert!(daemons[0].is_guarded);
+        assert!(daemons[1].name.ends_with("Unreachable"));
+        assert_eq!(daemons[1].status, DaemonStatus::Failed);
+        assert_eq!(daemons[1].restarted_times, 2);
+        assert!(daemons[1].uptime.is_none());
+        assert!(daemons[1].last_error.is_some());
+
+        let sleeper = runtime.clone();
+        let _task = moonbase
+            .spawn_task("cleanup", async move {
+                sleeper.sleep(Duration::from_secs(60)).await;
+            })
+            .unwrap();
+        let workloads = moonbase.workloads();
+        assert_eq!(workloads.len(), 3);
+        assert!(
+            matches!(&workloads[0], WorkloadInfo::Daemon(daemon) if daemon.name.ends_with("Listener"))
+        );
+        assert!(matches!(&workloads[2], WorkloadInfo::Task(task) if &*task.name == "cleanup"));
+        assert_eq!(
+            workloads
+                .iter()
+                .map(WorkloadInfo::is_running)
+                .collect::<Vec<_>>(),
+            [true, false, true]
+        );
+    });
+}
+
+#[test]
+fn test_child_daemons() {
+    run(|moonbase, runtime| async move {
+        let root_handle = moonbase.run_daemon::<Listener>().await.unwrap();
+        let child = moonbase.child();
+        let child_handle = child.run_daemon::<Listener>().await.unwrap();
+        runtime.run_until_stalled();
+        // the child runs its own daemon, the root's is left running
+        assert_eq!(root_handle.state(), DaemonStatus::Running);
+        assert_eq!(child_handle.state(), DaemonStatus::Running);
+        assert_eq!(child.daemons().len(), 1);
+        assert_eq!(moonbase.daemons().len(), 1);
+        drop(child);
+
+        let report = moonbase.shutdown(Duration::from_secs(1)).await;
+        assert!(report.is_clean());
+        assert_eq!(root_handle.state(), DaemonStatus::Terminated);
+        assert_eq!(child_handle.state(), DaemonStatus::Terminated);
+    });
+}
+
+#[test]
+fn test_daemon_events() {
+    run(|moonbase, _runtime| async move {
+        let mut events = moonbase.daemon_events();
+        let handle = moonbase.run_daemon::<Unreachable>().await.unwrap();
+        let mut kinds = Vec::new();
+        while let Some(event) = events.next().await {
+            assert_eq!(event.name, handle.name());
+            let terminated = matches!(event.kind, DaemonEventKind::Terminated(_));
+            kinds.push(event.kind);
+            if terminated {
+                break;
+            }
+        }
+        let kinds = kinds
+            .iter()
+            .map(|kind| match kind {
+                DaemonEventKind::Starting => "starting".to_string(),
+                DaemonEventKind::Running => "running".to_string(),
+                DaemonEventKind::Paused => "paused".to_string(),
+                DaemonEventKind::Failed(error) => format!("failed: {error}"),
+                DaemonEventKind::CooledDown(delay) => format!("cooled down {delay:?}"),
+                DaemonEventKind::Restarted(times) => format!("restarted {times}"),
+                DaemonEventKind::Terminated(status) => format!("terminated {status:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                "starting",
+                "running",
+                "failed: connection refused",
+                "starting",
+                "restarted 1",
+                "running",
+                "failed: connection refused",
+                "starting",
+                "restarted 2",
+                "running",
+                "failed: connection refused",
+                "starting",
+                "terminated Failed",
+            ]
+        );
+    });
+}
+
+/// one consumer per partition
+#[derive(Debug)]
+struct Consumer {
+    partition: u32,
+    runtime: RuntimeHandle,
+}
+
+impl TryExtractFrom<Moonbase> for Consumer {
+    type Error = RuntimeNotSet;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Consumer {
+            partition: 0,
+            runtime: context.runtime()?,
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<RuntimeHandle>()]
+    }
+}
+
+impl IntoFuture for Consumer {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let delay = Duration::from_secs(3600 + self.partition as u64);
+            self.runtime.sleep(delay).await;
+            self
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Consumer {}
+
+#[test]
+fn test_daemon_instances() {
+    run(|moonbase, _runtime| async move {
+        for partition in [1, 0] {
+            moonbase
+                .run_daemon_instance_with(partition.to_string(), move |context| async move {
+                    Ok(Consumer {
+                        partition,
+                        runtime: context.runtime()?,
+                    })
+                })
+                .await
+                .unwrap();
+        }
+        moonbase.run_daemon::<Consumer>().await.unwrap();
+        let instances = moonbase.daemon_instances::<Consumer>();
+        assert_eq!(
+            instances
+                .iter()
+                .map(|handle| handle.instance().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["0", "1"]
+        );
+        assert!(instances[0].name().ends_with("Consumer#0"));
+        assert_eq!(moonbase.daemons().len(), 3);
+
+        let killed = moonbase
+            .kill_daemon_instance::<Consumer>("1")
+            .await
+            .unwrap();
+        assert_eq!(killed.state(), DaemonStatus::Terminated);
+        assert_eq!(
+            moonbase
+                .get_daemon_instance::<Consumer>("0")
+                .unwrap()
+                .state(),
+            DaemonStatus::Running
+        );
+        assert_eq!(
+            moonbase.get_daemon_handle::<Consumer>().unwrap().state(),
+            DaemonStatus::Running
+        );
+        assert!(moonbase
+            .kill_daemon_instance::<Consumer>("2")
+            .await
+            .is_none());
+    });
+}
+
+#[derive(Debug, Clone, Default)]
+struct Ticks(Arc<AtomicUsize>);
+
+/// ticks every 5ms forever
+#[derive(Debug)]
+struct Ticker {
+    ticks: Ticks,
+    runtime: RuntimeHandle,
+}
+
+impl TryExtractFrom<Moonbase> for Ticker {
+    type Error = RuntimeNotSet;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Ticker {
+            ticks: context.get_resource::<Ticks>().unwrap_or_default(),
+            runtime: context.runtime()?,
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<RuntimeHandle>()]
+    }
+}
+
+impl IntoFuture for Ticker {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            loop {
+                self.runtime.sleep(Duration::from_millis(5)).await;
+                self.ticks.0.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Ticker {}
+
+#[test]
+fn test_pause_and_resume() {
+    run(|moonbase, runtime| async move {
+        let ticks = Ticks::default();
+        moonbase.set_resource(ticks.clone());
+        let mut events = moonbase.daemon_events();
+        let handle = moonbase.run_daemon::<Ticker>().await.unwrap();
+        runtime.sleep(Duration::from_millis(30)).await;
+        assert!(handle.pause());
+        assert!(!handle.pause());
+        while let Some(event) = events.next().await {
+            if matches!(event.kind, DaemonEventKind::Paused) {
+                break;
+            }
+        }
+        assert_eq!(handle.state(), DaemonStatus::Paused);
+        assert!(handle.uptime().is_none());
+        let paused_at = ticks.0.load(Ordering::SeqCst);
+        assert!(paused_at > 0);
+        runtime.sleep(Duration::from_millis(30)).await;
+        assert_eq!(ticks.0.load(Ordering::SeqCst), paused_at);
+
+        assert!(handle.resume());
+        runtime.sleep(Duration::from_millis(30)).await;
+        assert_eq!(handle.state(), DaemonStatus::Running);
+        // the uptime counts from the resume
+        assert_eq!(handle.uptime(), Some(Duration::from_millis(30)));
+        assert!(ticks.0.load(Ordering::SeqCst) > paused_at);
+        assert_eq!(handle.restarted_times(), 0);
+    });
+}
+
+#[tokio::test]
+async fn test_runtime_not_set() {
+    let moonbase = Moonbase::new();
+    let error = moonbase.run_daemon::<Listener>().await.unwrap_err();
+    assert!(error.downcast_ref::<RuntimeNotSet>().is_some());
+    assert!(moonbase.daemons().is_empty());
+
+    moonbase.set_runtime(Tokio::default());
+    let handle = moonbase.run_daemon::<Listener>().await.unwrap();
+    assert!(moonbase
+        .kill_all_daemons(Duration::from_secs(1))
+        .await
+        .is_empty());
+    assert_eq!(handle.state(), DaemonStatus::Terminated);
+}
@@ -0,0 +1,254 @@
+use std::{
+    future::IntoFuture,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use moonbase::{
+    components::{ComponentName, MoonbaseComponent},
+    context::{Context, ContextExt},
+    daemon::{Daemon, DaemonOutcome, DaemonStatus},
+    extract::{Extract, ExtractFrom, TryExtractFrom},
+    module::{Dependency, Module, ModuleGraph},
+    resource::{Resource, ResourceError},
+    runtime::{Runtime, TestRuntime},
+    Moonbase,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Database(&'static str);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Mailer;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Limit(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cache(usize);
+
+impl MoonbaseComponent for Cache {}
+
+#[derive(Debug, Extract)]
+struct UserService {
+    context: Moonbase,
+    #[extract(resource)]
+    database: Database,
+    #[extract(resource)]
+    mailer: Option<Mailer>,
+    #[extract(resource)]
+    sender: anyhow::Result<Mailer>,
+    #[extract(resource, default)]
+    limit: Limit,
+    #[extract(component = "users")]
+    cache: Cache,
+    database_resource: Result<Resource<Database>, ResourceError>,
+}
+
+#[derive(Debug, Extract)]
+struct Pair((Moonbase, Resource<Database>));
+
+#[derive(Debug, Extract)]
+#[extract(infallible)]
+struct Probe(Moonbase, #[extract(resource)] Option<Database>);
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    futures::executor::block_on(future)
+}
+
+#[test]
+fn test_derive_extract() {
+    let moonbase = Moonbase::new();
+    let error = block_on(UserService::try_extract_from(&moonbase)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "fail to extract field `database` of `UserService`"
+    );
+    assert!(format!("{error:#}").contains("Database"), "{error:#}");
+    // the optional, defaulted and `Result` fields are not required
+    assert_eq!(
+        <UserService as TryExtractFrom<Moonbase>>::requirements(),
+        [Dependency::resource::<Database>()]
+    );
+
+    // a tuple mixing fallible extractors
+    let error = block_on(Pair::try_extract_from(&moonbase)).unwrap_err();
+    assert_eq!(error.to_string(), "fail to extract field `0` of `Pair`");
+    assert!(error.downcast_ref::<ResourceError>().is_some(), "{error:#}");
+    assert_eq!(
+        <Pair as TryExtractFrom<Moonbase>>::requirements(),
+        [Dependency::resource::<Database>()]
+    );
+
+    moonbase.set_resource(Database("users"));
+    let Pair((_, Resource(database))) = block_on(Pair::try_extract_from(&moonbase)).unwrap();
+    assert_eq!(database, Database("users"));
+    let error = block_on(UserService::try_extract_from(&moonbase)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "fail to extract field `cache` of `UserService`"
+    );
+
+    moonbase.set_component(&ComponentName::new("users"), Cache(16));
+    let service = block_on(moonbase.extract::<Result<UserService, _>>()).unwrap();
+    assert_eq!(service.context.id(), moonbase.id());
+    assert_eq!(service.database, Database("users"));
+    assert_eq!(service.mailer, None);
+    let error = service.sender.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "fail to extract field `sender` of `UserService`"
+    );
+    assert_eq!(service.limit, Limit(0));
+    assert_eq!(service.cache, Cache(16));
+    assert!(service.database_resource.is_ok());
+
+    let Probe(context, database) = block_on(Probe::extract_from(&moonbase));
+    assert_eq!(context.id(), moonbase.id());
+    assert_eq!(database, Some(Database("users")));
+}
+
+#[moonbase::daemon(max_restarts = 3, cool_down = "1s")]
+async fn notifier(
+    #[extract(resource)] database: Database,
+    #[extract(resource)] limit: Option<Limit>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(limit.is_some(), "no limit for {database:?}");
+    Ok(())
+}
+
+#[derive(Debug, Extract)]
+struct Counter {
+    #[extract(resource)]
+    limit: Limit,
+}
+
+#[moonbase::daemon]
+impl Counter {
+    async fn run(mut self) -> DaemonOutcome<Self> {
+        self.limit.0 += 1;
+        // continue once from the limit set by `LimitModule`
+        match self.limit.0 {
+            6 => DaemonOutcome::Continue(self),
+            _ => DaemonOutcome::Stop,
+        }
+    }
+}
+
+#[moonbase::module(provides(Limit))]
+async fn limit_module(#[extract(resource)] database: Database, context: Moonbase) {
+    context.set_resource(Limit(database.0.len()));
+}
+
+#[moonbase::module(after(LimitModule))]
+async fn cache_module(#[extract(resource)] limit: Limit, context: Moonbase) {
+    context.set_component(&ComponentName::new("users"), Cache(limit.0));
+}
+
+#[test]
+fn test_attribute_macros() {
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(Database("users"));
+
+    let daemon = block_on(Notifier::try_extract_from(&moonbase)).unwrap();
+    assert_eq!(daemon.max_restart_time(), Some(3));
+    assert_eq!(daemon.cool_down_time(), Some(Duration::from_secs(1)));
+    assert_eq!(
+        <Notifier as TryExtractFrom<Moonbase>>::requirements(),
+        [Dependency::resource::<Database>()]
+    );
+    let outcome = block_on(daemon.into_future());
+    assert!(matches!(outcome, DaemonOutcome::Failed(_)));
+
+    assert_eq!(
+        LimitModule::dependencies(),
+        [Dependency::resource::<Database>()]
+    );
+    assert_eq!(
+        CacheModule::dependencies(),
+        [
+            Dependency::resource::<Limit>(),
+            Dependency::module::<LimitModule>()
+        ]
+    );
+    let built = block_on(
+        Moonbase::builder()
+            .resource(Database("users"))
+            .module(CacheModule)
+            .module(LimitModule)
+            .build(),
+    )
+    .unwrap();
+    assert_eq!(
+        built.get_component::<Cache>(&ComponentName::new("users")),
+        Some(Cache(5))
+    );
+
+    let daemon = block_on(Notifier::try_extract_from(&built)).unwrap();
+    assert!(matches!(
+        block_on(daemon.into_future()),
+        DaemonOutcome::Stop
+    ));
+    let counter = block_on(Counter::try_extract_from(&built)).unwrap();
+    let DaemonOutcome::Continue(counter) = block_on(counter.into_future()) else {
+        panic!("counter should continue");
+    };
+    assert!(matches!(
+        block_on(counter.into_future()),
+        DaemonOutcome::Stop
+    ));
+}
+
+#[derive(Debug, Clone, Default)]
+struct Attempts(Arc<AtomicUsize>);
+
+/// fails until its third run
+#[moonbase::daemon(max_restarts = 3, cool_down = "10ms")]
+async fn retrying(#[extract(resource)] attempts: Attempts) -> anyhow::Result<()> {
+    let attempt = attempts.0.fetch_add(1, Ordering::SeqCst) + 1;
+    anyhow::ensure!(attempt == 3, "attempt {attempt} fails");
+    Ok(())
+}
+
+#[moonbase::module(provides(Attempts))]
+async fn attempts_module(context: Moonbase) {
+    context.set_resource(Attempts::default());
+}
+
+#[test]
+fn test_macros_supervised() {
+    let runtime = TestRuntime::new();
+    runtime.block_on(async {
+        let moonbase = Moonbase::new();
+        moonbase.load_module(runtime.clone()).await.unwrap();
+        let error = moonbase.load_module(LimitModule).await.unwrap_err();
+        assert!(
+            format!("{error:#}").contains("fail to extract field `database` of `LimitModule`"),
+            "{error:#}"
+        );
+        let error = moonbase.run_daemon::<Retrying>().await.unwrap_err();
+        assert!(
+            format!("{error:#}").contains("fail to extract field `attempts` of `Retrying`"),
+            "{error:#}"
+        );
+
+        let mut modules = ModuleGraph::new();
+        modules.add(AttemptsModule);
+        moonbase.load_modules(modules).await.unwrap();
+        let handle = moonbase.run_daemon::<Retrying>().await.unwrap();
+        runtime
+            .timeout(Duration::from_secs(60), handle.wait())
+            .await
+            .expect("daemon should stop");
+        assert_eq!(handle.state(), DaemonStatus::Terminated);
+        assert_eq!(handle.restarted_times(), 2);
+        assert_eq!(runtime.elapsed(), Duration::from_millis(20));
+        let error = handle.last_error().unwrap();
+        assert_eq!(error.to_string(), "attempt 2 fails");
+        let attempts = moonbase.get_resource::<Attempts>().unwrap();
+        assert_eq!(attempts.0.load(Ordering::SeqCst), 3);
+    });
+}
@@ -0,0 +1,414 @@
+use std::{
+    convert::Infallible,
+    future::IntoFuture,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{Future, FutureExt};
+use moonbase::{
+    context::ContextExt,
+    daemon::{Daemon, DaemonStatus},
+    extract::TryExtractFrom,
+    module::{Dependency, Module, ModuleGraph, ModuleLoadError},
+    runtime::{RuntimeHandle, Tokio},
+    signal::SignalKey,
+    task::TaskStatus,
+    Moonbase,
+};
+
+#[derive(Debug, Clone, Default)]
+struct LoadLog(Arc<Mutex<Vec<&'static str>>>);
+
+#[derive(Debug, Clone)]
+struct Database;
+
+struct DatabaseModule;
+struct UserModule;
+struct WebModule;
+
+impl Module<Moonbase> for DatabaseModule {
+    fn provides() -> Vec<Dependency> {
+        vec![Dependency::resource::<Database>()]
+    }
+    async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
+        context.set_resource(Database);
+        let log = context.get_resource::<LoadLog>().unwrap();
+        log.0.lock().unwrap().push("database");
+        Ok(())
+    }
+    async fn finalize(context: Moonbase) -> anyhow::Result<()> {
+        context.remove_resource::<Database>();
+        let log = context.get_resource::<LoadLog>().unwrap();
+        log.0.lock().unwrap().push("~database");
+        Ok(())
+    }
+}
+
+impl Module<Moonbase> for UserModule {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::resource::<Database>()]
+    }
+    async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
+        anyhow::ensure!(context.has_resource::<Database>(), "database not ready");
+        let log = context.get_resource::<LoadLog>().unwrap();
+        log.0.lock().unwrap().push("user");
+        Ok(())
+    }
+}
+
+impl Module<Moonbase> for WebModule {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::module::<UserModule>()]
+    }
+    async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
+        let log = context.get_resource::<LoadLog>().unwrap();
+        log.0.lock().unwrap().push("web");
+        Ok(())
+    }
+    async fn finalize(context: Moonbase) -> anyhow::Result<()> {
+        let log = context.get_resource::<LoadLog>().unwrap();
+        log.0.lock().unwrap().push("~web");
+        anyhow::bail!("web is broken")
+    }
+}
+
+#[tokio::test]
+async fn test_load_in_dependency_order() {
+    let log = LoadLog::default();
+    let moonbase = Moonbase::builder()
+        .resource(log.clone())
+        .module(WebModule)
+        .module(UserModule)
+        .module(DatabaseModule)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(*log.0.lock().unwrap(), vec!["database", "user", "web"]);
+    let loaded = moonbase
+        .loaded_modules()
+        .into_iter()
+        .map(|module| module.name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        loaded,
+        vec![
+            DatabaseModule::module_name(),
+            UserModule::module_name(),
+            WebModule::module_name()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_missing_dependency() {
+    let error = Moonbase::builder()
+        .resource(LoadLog::default())
+        .module(WebModule)
+        .module(UserModule)
+        .build()
+        .await
+        .unwrap_err();
+    let ModuleLoadError::Missing(missing) = &error else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].module, UserModule::module_name());
+    assert_eq!(missing[0].dependency, Dependency::resource::<Database>());
+}
+
+#[tokio::test]
+async fn test_load_module_is_finalized() {
+    let log = LoadLog::default();
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(log.clone());
+    moonbase.load_module(DatabaseModule).await.unwrap();
+    assert!(moonbase.is_module_loaded(&std::any::TypeId::of::<DatabaseModule>()));
+    assert!(moonbase.finalize_modules().await.is_empty());
+    assert_eq!(*log.0.lock().unwrap(), vec!["database", "~database"]);
+    assert!(!moonbase.has_resource::<Database>());
+}
+
+#[tokio::test]
+async fn test_tokio_provides_runtime_handle() {
+    struct NeedsRuntime;
+    impl Module<Moonbase> for NeedsRuntime {
+        fn dependencies() -> Vec<Dependency> {
+            vec![Dependency::resource::<RuntimeHandle>()]
+        }
+        async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
+            context.runtime()?;
+            Ok(())
+        }
+    }
+    Moonbase::builder()
+        .module(NeedsRuntime)
+        .module(Tokio::default())
+        .build()
+        .await
+        .unwrap();
+}
+
+struct Chicken;
+struct Egg;
+
+impl Module<Moonbase> for Chicken {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::module::<Egg>()]
+    }
+    async fn initialize(self, _context: Moonbase) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl Module<Moonbase> for Egg {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::module::<Chicken>()]
+    }
+    async fn initialize(self, _context: Moonbase) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_cycle() {
+    let mut graph = ModuleGraph::new();
+    graph.add(Chicken).add(Egg);
+    let error = graph.resolve(&Moonbase::new()).unwrap_err();
+    let ModuleLoadError::Cycle(path) = &error else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(
+        *path,
+        vec![
+            Chicken::module_name(),
+            Egg::module_name(),
+            Chicken::module_name()
+        ]
+    );
+}
+
+struct Ouroboros;
+
+impl Module<Moonbase> for Ouroboros {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::resource::<Database>()]
+    }
+    fn provides() -> Vec<Dependency> {
+        vec![Dependency::resource::<Database>()]
+    }
+    async fn initialize(self, _context: Moonbase) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_self_cycle() {
+    let mut graph = ModuleGraph::new();
+    graph.add(Ouroboros);
+    let error = graph.resolve(&Moonbase::new()).unwrap_err();
+    let ModuleLoadError::Cycle(path) = &error else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(
+        *path,
+        vec![Ouroboros::module_name(), Ouroboros::module_name()]
+    );
+}
+
+/// declares a database but never sets it
+struct Liar;
+
+impl Module<Moonbase> for Liar {
+    fn provides() -> Vec<Dependency> {
+        vec![Dependency::resource::<Database>()]
+    }
+    async fn initialize(self, _context: Moonbase) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_not_provided() {
+    let error = Moonbase::builder()
+        .resource(LoadLog::default())
+        .module(Liar)
+        .module(UserModule)
+        .build()
+        .await
+        .unwrap_err();
+    let ModuleLoadError::NotProvided { module, dependency } = &error else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(*module, Liar::module_name());
+    assert_eq!(*dependency, Dependency::resource::<Database>());
+
+    let moonbase = Moonbase::new();
+    let error = moonbase.load_module(Liar).await.unwrap_err();
+    assert!(error.to_string().contains("doesn't set"), "{error}");
+    // registered anyway, so it's finalized
+    assert!(moonbase.is_module_loaded(&std::any::TypeId::of::<Liar>()));
+}
+
+#[tokio::test]
+async fn test_already_loaded() {
+    let log = LoadLog::default();
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(log.clone());
+    moonbase.load_module(DatabaseModule).await.unwrap();
+    let error = moonbase.load_module(DatabaseModule).await.unwrap_err();
+    assert!(error.to_string().contains("already loaded"), "{error}");
+    let mut graph = ModuleGraph::new();
+    graph.add(DatabaseModule);
+    let error = moonbase.load_modules(graph).await.unwrap_err();
+    assert!(
+        matches!(error, ModuleLoadError::AlreadyLoaded { .. }),
+        "{error}"
+    );
+    assert!(moonbase.finalize_modules().await.is_empty());
+    assert_eq!(*log.0.lock().unwrap(), vec!["database", "~database"]);
+}
+
+#[derive(Debug)]
+struct Heartbeat;
+
+impl TryExtractFrom<Moonbase> for Heartbeat {
+    type Error = Infallible;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Heartbeat)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl IntoFuture for Heartbeat {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            self
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Heartbeat {}
+
+#[tokio::test]
+async fn test_shutdown() {
+    let log = LoadLog::default();
+    let moonbase = Moonbase::builder()
+        .resource(log.clone())
+        .module(Tokio::default())
+        .module(WebModule)
+        .module(UserModule)
+        .module(DatabaseModule)
+        .build()
+        .await
+        .unwrap();
+    let handle = moonbase.run_daemon::<Heartbeat>().await.unwrap();
+    let report = moonbase.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(handle.state(), DaemonStatus::Terminated);
+    assert!(report.timed_out_daemons.is_empty());
+    assert_eq!(report.failed_modules.len(), 1);
+    assert_eq!(report.failed_modules[0].0, WebModule::module_name());
+    assert_eq!(
+        *log.0.lock().unwrap(),
+        vec!["database", "user", "web", "~web", "~database"]
+    );
+    assert!(moonbase.loaded_modules().is_empty());
+    assert!(!moonbase.has_resource::<Database>());
+}
+
+#[tokio::test]
+async fn test_child_shutdown() {
+    let log = LoadLog::default();
+    let moonbase = Moonbase::builder()
+        .resource(log.clone())
+        .module(Tokio::default())
+        .module(DatabaseModule)
+        .build()
+        .await
+        .unwrap();
+    let handle = moonbase.run_daemon::<Heartbeat>().await.unwrap();
+    let task = moonbase
+        .spawn_task("parent", tokio::time::sleep(Duration::from_secs(3600)))
+        .unwrap();
+    let child = moonbase.child();
+    let child_handle = child.run_daemon::<Heartbeat>().await.unwrap();
+    let child_task = child
+        .spawn_task("child", tokio::time::sleep(Duration::from_secs(3600)))
+        .unwrap();
+
+    let shutdown = moonbase.get_or_insert_signal(SignalKey::SHUTDOWN).recv();
+    // only what's run in the child is stopped
+    let report = child.shutdown(Duration::from_secs(1)).await;
+    assert!(report.is_clean());
+    assert_eq!(report.cancelled_tasks, ["child"]);
+    assert_eq!(child_handle.state(), DaemonStatus::Terminated);
+    assert_eq!(child_task.status(), TaskStatus::Cancelled);
+    assert_eq!(handle.state(), DaemonStatus::Running);
+    assert_eq!(task.status(), TaskStatus::Running);
+    assert!(shutdown.now_or_never().is_none());
+    assert_eq!(moonbase.loaded_modules().len(), 2);
+    assert!(moonbase.has_resource::<Database>());
+    assert_eq!(*log.0.lock().unwrap(), vec!["database"]);
+
+    let report = moonbase.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(report.cancelled_tasks, ["parent"]);
+    assert_eq!(handle.state(), DaemonStatus::Terminated);
+}
+
+#[tokio::test]
+async fn test_run_until_terminated() {
+    let moonbase = Moonbase::builder()
+        .module(Tokio::default())
+        .build()
+        .await
+        .unwrap();
+    let handle = moonbase.run_daemon::<Heartbeat>().await.unwrap();
+    let terminated = moonbase.run_until_terminated(Duration::from_secs(1));
+    moonbase.trigger_signal(&SignalKey::SHUTDOWN);
+    let report = terminated.await.unwrap();
+    assert!(report.is_clean());
+    assert_eq!(handle.state(), DaemonStatus::Terminated);
+}
+
+/// Sends SIGHUP to its own process, so it runs alone in a subprocess of the test binary.
+#[cfg(unix)]
+#[test]
+fn test_run_until_os_signal() {
+    const CHILD: &str = "MOONBASE_OS_SIGNAL_TEST";
+    if std::env::var_os(CHILD).is_none() {
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["test_run_until_os_signal", "--exact", "--test-threads=1"])
+            .env(CHILD, "1")
+            .status()
+            .unwrap();
+        assert!(status.success(), "the subprocess failed: {status}");
+        return;
+    }
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let moonbase = Moonbase::builder()
+            .module(Tokio::default())
+            .build()
+            .await
+            .unwrap();
+        let handle = moonbase.run_daemon::<Heartbeat>().await.unwrap();
+        let terminated = moonbase.run_until_terminated(Duration::from_secs(1));
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let report = tokio::time::timeout(Duration::from_secs(5), terminated)
+            .await
+            .expect("terminated by SIGHUP")
+            .unwrap();
+        assert!(report.is_clean());
+        assert_eq!(handle.state(), DaemonStatus::Terminated);
+    });
+}
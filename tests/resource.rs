@@ -0,0 +1,192 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use moonbase::{
+    components::ComponentName,
+    context::{Context, ContextExt},
+    resource::{Resource, ResourceError, SharedResource, Watch},
+    signal::SignalKey,
+    Moonbase,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pool(usize);
+
+async fn yield_now() {
+    let mut yielded = false;
+    futures::future::poll_fn(|cx| {
+        if std::mem::replace(&mut yielded, true) {
+            return std::task::Poll::Ready(());
+        }
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await;
+}
+
+#[test]
+fn test_lazy_resource() {
+    let moonbase = Moonbase::new();
+    let built = Arc::new(AtomicUsize::new(0));
+    let counter = built.clone();
+    moonbase.set_resource_factory(move |_| {
+        let counter = counter.clone();
+        async move {
+            // yield once, so concurrent callers overlap
+            yield_now().await;
+            let times = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            if times == 1 {
+                anyhow::bail!("connection refused");
+            }
+            Ok(Pool(times))
+        }
+    });
+    assert!(moonbase.has_resource::<Pool>());
+    assert_eq!(moonbase.get_resource::<Pool>(), None);
+    futures::executor::block_on(async {
+        let error = moonbase.get_or_init_resource::<Pool>().await.unwrap_err();
+        assert!(format!("{error:#}").contains("connection refused"));
+
+        let (a, b, Resource(c)) = futures::join!(
+            moonbase.get_or_init_resource::<Pool>(),
+            moonbase.get_or_init_resource::<Pool>(),
+            moonbase.extract::<Resource<Pool>>(),
+        );
+        assert_eq!(a.unwrap(), Pool(2));
+        assert_eq!(b.unwrap(), Pool(2));
+        assert_eq!(c, Pool(2));
+        assert_eq!(moonbase.get_resource::<Pool>(), Some(Pool(2)));
+
+        // removed resources are built again
+        moonbase.remove_resource::<Pool>();
+        let child = moonbase.child();
+        assert_eq!(child.get_or_init_resource::<Pool>().await.unwrap(), Pool(3));
+    });
+    assert_eq!(built.load(Ordering::SeqCst), 3);
+}
+
+#[derive(Debug, PartialEq)]
+struct Config {
+    port: u16,
+}
+
+#[test]
+fn test_watch_resource() {
+    let moonbase = Moonbase::new();
+    let watch = moonbase.set_watch_resource(Config { port: 80 });
+    let mut watcher = moonbase.watch_resource::<Config>().unwrap();
+    let signal = moonbase
+        .get_signal(&SignalKey::symbol::<Watch<Config>>())
+        .unwrap();
+    let notified = signal.recv();
+    assert_eq!(watcher.current().port, 80);
+
+    // replacing the same resource keeps the watchers
+    moonbase.set_watch_resource(Config { port: 8080 });
+    let previous = watch.replace(Config { port: 8443 });
+    assert_eq!(previous.port, 8080);
+    assert_eq!(watch.version(), 2);
+    futures::executor::block_on(async {
+        notified.await;
+        // replacements are coalesced
+        assert_eq!(watcher.changed().await.port, 8443);
+        let (changed, ()) = futures::join!(watcher.changed(), async {
+            yield_now().await;
+            watch.replace(Config { port: 443 });
+        });
+        assert_eq!(*changed, Config { port: 443 });
+    });
+    let Resource(watch) =
+        futures::executor::block_on(moonbase.extract::<Resource<Watch<Config>>>());
+    assert_eq!(watch.load().port, 443);
+}
+
+#[test]
+fn test_set_watch_resource_concurrently() {
+    let moonbase = Moonbase::new();
+    let watches = std::thread::scope(|scope| {
+        let moonbase = &moonbase;
+        let threads = (0..8)
+            .map(|port| scope.spawn(move || moonbase.set_watch_resource(Config { port })))
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    // a single watch is set, replaced by every other caller
+    let watch = moonbase.get_watch_resource::<Config>().unwrap();
+    assert_eq!(watch.version(), 7);
+    for other in watches {
+        assert_eq!(other.version(), 7);
+    }
+}
+
+/// not `Clone`
+#[derive(Debug)]
+struct Connection {
+    id: u32,
+}
+
+#[test]
+fn test_shared_resource() {
+    let moonbase = Moonbase::new();
+    let connection = Arc::new(Connection { id: 1 });
+    moonbase.set_resource_arc(connection.clone());
+    let shared = moonbase.get_resource_arc::<Connection>().unwrap();
+    assert!(Arc::ptr_eq(&shared, &connection));
+
+    moonbase.set_resource_factory(|_| async { Ok(Connection { id: 2 }) });
+    moonbase.remove_resource_arc::<Connection>();
+    futures::executor::block_on(async {
+        let SharedResource(built) = moonbase.extract::<SharedResource<Connection>>().await;
+        assert_eq!(built.id, 2);
+        let cached = moonbase.get_resource_arc::<Connection>().unwrap();
+        assert!(Arc::ptr_eq(&built, &cached));
+    });
+
+    let name = ComponentName::<Connection>::new("primary");
+    moonbase.set_component_arc(&name, connection.clone());
+    let component = moonbase.get_component_arc(&name).unwrap();
+    assert!(Arc::ptr_eq(&component, &connection));
+    assert!(moonbase.has_component(&name));
+    assert!(moonbase.remove_component_arc(&name).is_some());
+    assert!(!moonbase.has_component(&name));
+}
+
+#[test]
+fn test_resource_not_found() {
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(Pool(1));
+    moonbase.set_resource_factory(|_| async { Ok(Connection { id: 1 }) });
+    let child = moonbase.child();
+    let error =
+        futures::executor::block_on(child.try_extract::<Resource<Arc<Pool>>>()).unwrap_err();
+    let ResourceError::NotFound(not_found) = &error else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(not_found.type_name, std::any::type_name::<Arc<Pool>>());
+    assert_eq!(not_found.contexts, [child.id(), moonbase.id()]);
+    assert_eq!(not_found.similar, [std::any::type_name::<Pool>()]);
+    let message = error.to_string();
+    assert!(message.contains(&format!("#{} -> #0", child.id())));
+    assert!(message.contains("similar registered types"));
+}
+
+#[test]
+fn test_call_with_resource() {
+    let moonbase = Moonbase::new();
+    moonbase.set_resource(Pool(1));
+    let Resource(pool) =
+        futures::executor::block_on(moonbase.call(|pool: Resource<Pool>| async move { pool }));
+    assert_eq!(pool, Pool(1));
+}
+
+#[test]
+#[should_panic(expected = "Pool` not found")]
+fn test_extract_missing_resource() {
+    let moonbase = Moonbase::new();
+    futures::executor::block_on(moonbase.extract::<Resource<Pool>>());
+}
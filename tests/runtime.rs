@@ -0,0 +1,243 @@
+use std::{convert::Infallible, future::IntoFuture, pin::Pin, time::Duration};
+
+use futures::{Future, StreamExt};
+use moonbase::{
+    daemon::{Backoff, Daemon, DaemonStatus, RestartPolicy},
+    extract::TryExtractFrom,
+    module::Dependency,
+    runtime::{Elapsed, JoinError, Runtime, RuntimeHandle, TestRuntime},
+    task::{TaskInfo, TaskStatus, WorkloadInfo},
+    Moonbase,
+};
+
+#[test]
+fn test_virtual_clock() {
+    let runtime = TestRuntime::new();
+    let (tx, rx) = futures::channel::oneshot::channel();
+    let sleeper = runtime.clone();
+    runtime.spawn(async move {
+        sleeper.sleep(Duration::from_secs(60)).await;
+        let _ = tx.send(sleeper.elapsed());
+    });
+    runtime.advance(Duration::from_secs(59));
+    assert_eq!(runtime.elapsed(), Duration::from_secs(59));
+    let woken_at = runtime.block_on(rx).unwrap();
+    assert_eq!(woken_at, Duration::from_secs(60));
+    runtime.block_on(runtime.sleep(Duration::from_secs(3600)));
+    assert_eq!(runtime.elapsed(), Duration::from_secs(3660));
+}
+
+/// works for 100ms then fails
+#[derive(Debug)]
+struct Worker {
+    runtime: RuntimeHandle,
+}
+
+impl TryExtractFrom<Moonbase> for Worker {
+    type Error = Infallible;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Worker {
+            runtime: context.runtime().unwrap(),
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<RuntimeHandle>()]
+    }
+}
+
+impl IntoFuture for Worker {
+    type Output = anyhow::Result<Self>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            self.runtime.sleep(Duration::from_millis(100)).await;
+            anyhow::bail!("worker failed")
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Worker {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new()
+            .max_restarts(3)
+            .backoff(Backoff::exponential(
+                Duration::from_secs(1),
+                Duration::from_secs(60),
+            ))
+    }
+}
+
+#[test]
+fn test_cool_down_timing() {
+    let runtime = TestRuntime::new();
+    runtime.block_on(async {
+        let moonbase = Moonbase::builder()
+            .module(runtime.clone())
+            .build()
+            .await
+            .unwrap();
+        let handle = moonbase.run_daemon::<Worker>().await.unwrap();
+        runtime.advance(Duration::from_millis(1100));
+        assert_eq!(handle.restarted_times(), 1);
+        assert_eq!(handle.state(), DaemonStatus::Running);
+        runtime.advance(Duration::from_millis(50));
+        assert_eq!(handle.uptime(), Some(Duration::from_millis(50)));
+        handle.wait().await;
+        assert_eq!(handle.state(), DaemonStatus::Failed);
+        assert_eq!(handle.restarted_times(), 3);
+        // 4 runs of 100ms, and cool downs of 1s, 2s and 4s
+        assert_eq!(runtime.elapsed(), Duration::from_millis(7400));
+
+        let handle = moonbase.run_daemon::<Worker>().await.unwrap();
+        runtime.advance(Duration::from_millis(50));
+        let report = moonbase.shutdown(Duration::from_secs(10)).await;
+        assert!(report.is_clean());
+        assert_eq!(handle.state(), DaemonStatus::Terminated);
+        assert_eq!(runtime.elapsed(), Duration::from_millis(7450));
+    });
+}
+
+#[test]
+fn test_join_handle() {
+    let runtime = TestRuntime::new();
+    runtime.block_on(async {
+        let handle = runtime.spawn(async { 42 });
+        assert_eq!(handle.await, Ok(42));
+
+        let sleeper = runtime.clone();
+        let handle = runtime.spawn(async move {
+            sleeper.sleep(Duration::from_secs(1)).await;
+            42
+        });
+        handle.abort();
+        assert_eq!(handle.await, Err(JoinError::Aborted));
+
+        let handle = runtime.spawn(async { panic!("boom") });
+        assert_eq!(handle.await, Err(JoinError::Panicked("boom".to_string())));
+
+        let handle = runtime.spawn_blocking(|| (1..=10).sum::<i32>());
+        assert_eq!(handle.await, Ok(55));
+    });
+    assert_eq!(runtime.elapsed(), Duration::ZERO);
+}
+
+#[test]
+fn test_timeout_and_interval() {
+    let runtime = TestRuntime::new();
+    runtime.block_on(async {
+        let slow = runtime.sleep(Duration::from_secs(2));
+        let result = runtime.timeout(Duration::from_secs(1), slow).await;
+        assert_eq!(result, Err(Elapsed));
+        assert_eq!(runtime.elapsed(), Duration::from_secs(1));
+
+        let fast = async { 42 };
+        let result = runtime.timeout(Duration::from_secs(1), fast).await;
+        assert_eq!(result, Ok(42));
+
+        let started_at = runtime.now();
+        let ticks = runtime
+            .interval(Duration::from_millis(100))
+            .take(4)
+            .map(|tick| tick - started_at)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            ticks,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(300)
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_dropped_timer() {
+    let runtime = TestRuntime::new();
+    let fast = runtime.sleep(Duration::from_millis(10));
+    let result = runtime.block_on(runtime.timeout(Duration::from_secs(1), fast));
+    assert_eq!(result, Ok(()));
+    // the clock doesn't jump to the deadline of the dropped timeout
+    let stalled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        runtime.block_on(futures::future::pending::<()>())
+    }));
+    assert!(stalled.is_err());
+    assert_eq!(runtime.elapsed(), Duration::from_millis(10));
+}
+
+#[test]
+fn test_tasks() {
+    let runtime = TestRuntime::new();
+    let moonbase = Moonbase::new();
+    moonbase.set_runtime(runtime.clone());
+    runtime.block_on(async {
+        let sleeper = runtime.clone();
+        let quick = moonbase
+            .spawn_task("quick", async move {
+                sleeper.sleep(Duration::from_secs(1)).await;
+                42
+            })
+            .unwrap();
+        let sleeper = runtime.clone();
+        let slow = moonbase
+            .spawn_task("slow", async move {
+                sleeper.sleep(Duration::from_secs(60)).await;
+            })
+            .unwrap();
+        let sleeper = runtime.clone();
+        let forgotten = moonbase
+            .spawn_task("forgotten", async move {
+                sleeper.sleep(Duration::from_secs(60)).await;
+            })
+            .unwrap();
+        let statuses = |tasks: Vec<TaskInfo>| {
+            tasks
+                .iter()
+                .map(|task| (task.name.to_string(), task.status))
+                .collect::<Vec<_>>()
+        };
+        let running = TaskStatus::Running;
+        assert_eq!(
+            statuses(moonbase.tasks()),
+            [
+                ("quick".into(), running),
+                ("slow".into(), running),
+                ("forgotten".into(), running)
+            ]
+        );
+
+        assert_eq!(quick.status(), TaskStatus::Running);
+        assert_eq!(quick.await, Ok(42));
+        assert!(slow.cancel());
+        assert!(!slow.cancel());
+        assert_eq!(slow.status(), TaskStatus::Cancelled);
+        assert_eq!(slow.await, Err(JoinError::Aborted));
+        let panicked = moonbase.spawn_task("panicked", async { panic!("boom") });
+        assert!(panicked.unwrap().await.is_err());
+        assert_eq!(
+            statuses(moonbase.tasks()),
+            [
+                ("quick".into(), TaskStatus::Finished),
+                ("slow".into(), TaskStatus::Cancelled),
+                ("forgotten".into(), running),
+                ("panicked".into(), TaskStatus::Panicked)
+            ]
+        );
+        let running = moonbase
+            .workloads()
+            .into_iter()
+            .filter(WorkloadInfo::is_running)
+            .map(|workload| workload.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(running, ["forgotten"]);
+
+        let report = moonbase.shutdown(Duration::from_secs(1)).await;
+        assert!(report.is_clean());
+        assert_eq!(report.cancelled_tasks, ["forgotten"]);
+        assert_eq!(forgotten.status(), TaskStatus::Cancelled);
+        assert!(moonbase.workloads().iter().all(|task| !task.is_running()));
+    });
+    assert_eq!(runtime.elapsed(), Duration::from_secs(1));
+}
@@ -0,0 +1,219 @@
+use std::{
+    convert::Infallible,
+    future::IntoFuture,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use futures::Future;
+use moonbase::{
+    daemon::{Backoff, Daemon, DaemonStatus, GroupStrategy, RestartPolicy},
+    extract::TryExtractFrom,
+    module::{Dependency, Module},
+    runtime::{Elapsed, JoinError, Runtime, RuntimeHandle, Smol},
+    Moonbase,
+};
+
+#[derive(Debug)]
+struct Flaky;
+
+impl TryExtractFrom<Moonbase> for Flaky {
+    type Error = Infallible;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Flaky)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl IntoFuture for Flaky {
+    type Output = anyhow::Result<Self>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            smol::Timer::after(Duration::from_millis(5)).await;
+            anyhow::bail!("flaky")
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Flaky {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new()
+            .max_restarts(2)
+            .backoff(Backoff::Fixed(Duration::from_millis(5)))
+    }
+}
+
+/// panics on every run
+#[derive(Debug)]
+struct Panicky;
+
+impl TryExtractFrom<Moonbase> for Panicky {
+    type Error = Infallible;
+    async fn try_extract_from(_context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Panicky)
+    }
+    fn requirements() -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+impl IntoFuture for Panicky {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { panic!("boom") })
+    }
+}
+
+impl Daemon<Moonbase> for Panicky {
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy::new().max_restarts(1)
+    }
+}
+
+/// sleeps on the runtime of the context until killed
+#[derive(Debug)]
+struct Sleeper {
+    runtime: RuntimeHandle,
+}
+
+impl TryExtractFrom<Moonbase> for Sleeper {
+    type Error = moonbase::runtime::RuntimeNotSet;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        Ok(Sleeper {
+            runtime: context.runtime()?,
+        })
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<RuntimeHandle>()]
+    }
+}
+
+impl IntoFuture for Sleeper {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            self.runtime.sleep(Duration::from_secs(3600)).await;
+            self
+        })
+    }
+}
+
+impl Daemon<Moonbase> for Sleeper {}
+
+async fn moonbase() -> Moonbase {
+    Moonbase::builder()
+        .module(Smol::default())
+        .build()
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_smol_runtime() {
+    smol::block_on(async {
+        let moonbase = moonbase().await;
+        let handle = moonbase.run_daemon::<Flaky>().await.unwrap();
+        handle.wait().await;
+        assert_eq!(handle.state(), DaemonStatus::Failed);
+        assert_eq!(handle.restarted_times(), 2);
+        let report = moonbase.shutdown(Duration::from_secs(1)).await;
+        assert!(report.is_clean());
+    });
+}
+
+#[test]
+fn test_sleep_and_timeout() {
+    smol::block_on(async {
+        let runtime = Smol::default();
+        let started_at = Instant::now();
+        runtime.sleep(Duration::from_millis(20)).await;
+        assert!(started_at.elapsed() >= Duration::from_millis(20));
+
+        let slow = runtime.sleep(Duration::from_secs(60));
+        let result = runtime.timeout(Duration::from_millis(10), slow).await;
+        assert_eq!(result, Err(Elapsed));
+        let result = runtime.timeout(Duration::from_secs(1), async { 42 }).await;
+        assert_eq!(result, Ok(42));
+
+        assert_eq!(runtime.spawn(async { 42 }).await, Ok(42));
+        let sleeper = runtime.clone();
+        let handle = runtime.spawn(async move {
+            sleeper.sleep(Duration::from_secs(60)).await;
+        });
+        handle.abort();
+        assert_eq!(handle.await, Err(JoinError::Aborted));
+        let handle = runtime.spawn_blocking(|| (1..=10).sum::<i32>());
+        assert_eq!(handle.await, Ok(55));
+    });
+}
+
+#[test]
+fn test_panic() {
+    smol::block_on(async {
+        let moonbase = moonbase().await;
+        let handle = moonbase.run_daemon::<Panicky>().await.unwrap();
+        handle.wait().await;
+        assert_eq!(handle.state(), DaemonStatus::Panicked);
+        assert_eq!(handle.restarted_times(), 1);
+        assert_eq!(handle.last_error().unwrap().panic_message(), Some("boom"));
+    });
+}
+
+#[test]
+fn test_one_for_all() {
+    smol::block_on(async {
+        let moonbase = moonbase().await;
+        let handles = moonbase
+            .daemon_group("sleepers")
+            .strategy(GroupStrategy::OneForAll)
+            .daemon::<Sleeper>()
+            .daemon::<Flaky>()
+            .run()
+            .await
+            .unwrap();
+        for handle in &handles {
+            handle.wait().await;
+            assert_eq!(handle.restarted_times(), 2);
+        }
+        assert_eq!(handles[0].state(), DaemonStatus::Terminated);
+        assert_eq!(handles[1].state(), DaemonStatus::Failed);
+    });
+}
+
+#[test]
+fn test_shutdown() {
+    smol::block_on(async {
+        let moonbase = moonbase().await;
+        let handle = moonbase.run_daemon::<Sleeper>().await.unwrap();
+        let report = moonbase.shutdown(Duration::from_secs(1)).await;
+        assert!(report.is_clean());
+        assert_eq!(handle.state(), DaemonStatus::Terminated);
+    });
+}
+
+#[test]
+fn test_smol_provides_runtime_handle() {
+    struct NeedsRuntime;
+    impl Module<Moonbase> for NeedsRuntime {
+        fn dependencies() -> Vec<Dependency> {
+            vec![Dependency::resource::<RuntimeHandle>()]
+        }
+        async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
+            context.runtime()?;
+            Ok(())
+        }
+    }
+    smol::block_on(async {
+        Moonbase::builder()
+            .module(NeedsRuntime)
+            .module(Smol::default())
+            .build()
+            .await
+            .unwrap();
+    });
+}
@@ -0,0 +1,210 @@
+use std::{future::IntoFuture, pin::Pin};
+
+use futures::Future;
+use moonbase::{
+    daemon::Daemon,
+    extract::TryExtractFrom,
+    module::{Dependency, Module},
+    resource::{Resource, ResourceError, SharedResource},
+    validate::{MissingRequirement, Requirer},
+    Moonbase,
+};
+
+#[derive(Debug, Clone)]
+struct Config;
+
+#[derive(Debug, Clone)]
+struct Pool;
+
+#[derive(Debug, Clone)]
+struct Mailer;
+
+#[derive(Debug, Clone)]
+struct Cache;
+
+async fn list_users(
+    _config: Resource<Config>,
+    _pool: SharedResource<Pool>,
+    _cache: Result<Resource<Cache>, ResourceError>,
+    _mailer: Option<Resource<Mailer>>,
+) {
+}
+
+#[derive(Debug)]
+struct Notifier;
+
+impl TryExtractFrom<Moonbase> for Notifier {
+    type Error = ResourceError;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        context.get_or_init_resource::<Mailer>().await?;
+        Ok(Notifier)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<Mailer>()]
+    }
+}
+
+impl IntoFuture for Notifier {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { self })
+    }
+}
+
+impl Daemon<Moonbase> for Notifier {}
+
+struct PoolModule;
+
+impl Module<Moonbase> for PoolModule {
+    fn dependencies() -> Vec<Dependency> {
+        vec![Dependency::resource::<Config>()]
+    }
+    fn provides() -> Vec<Dependency> {
+        vec![Dependency::resource::<Pool>()]
+    }
+    async fn initialize(self, context: Moonbase) -> anyhow::Result<()> {
+        context.set_resource(Pool);
+        Ok(())
+    }
+}
+
+/// Loaded after the pool, and requires what it provides.
+struct ReportModule;
+
+impl Module<Moonbase> for ReportModule {
+    fn dependencies() -> Vec<Dependency> {
+        vec![
+            Dependency::module::<PoolModule>(),
+            Dependency::resource::<Pool>(),
+        ]
+    }
+    async fn initialize(self, _context: Moonbase) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the pool by hand, listing it in its requirements.
+#[derive(Debug)]
+struct Auditor;
+
+impl TryExtractFrom<Moonbase> for Auditor {
+    type Error = ResourceError;
+    async fn try_extract_from(context: &Moonbase) -> Result<Self, Self::Error> {
+        context.get_or_init_resource::<Pool>().await?;
+        Ok(Auditor)
+    }
+    fn requirements() -> Vec<Dependency> {
+        vec![Dependency::resource::<Pool>()]
+    }
+}
+
+impl IntoFuture for Auditor {
+    type Output = Self;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self> + Send>>;
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { self })
+    }
+}
+
+impl Daemon<Moonbase> for Auditor {}
+
+#[test]
+fn test_validate() {
+    let moonbase = Moonbase::new();
+    let validation = moonbase
+        .validate()
+        .handler(&list_users)
+        .daemon::<Notifier>();
+    // an `Option` or a `Result` is not required
+    let missing = validation.missing();
+    let names = missing
+        .iter()
+        .map(|missing| missing.dependency.name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            std::any::type_name::<Config>(),
+            std::any::type_name::<Pool>(),
+            std::any::type_name::<Mailer>(),
+        ]
+    );
+    assert!(matches!(missing[0].required_by, Requirer::Handler(_)));
+    assert_eq!(
+        missing[2],
+        MissingRequirement {
+            required_by: Requirer::Daemon(std::any::type_name::<Notifier>()),
+            dependency: Dependency::resource::<Mailer>(),
+        }
+    );
+    let error = validation.check().unwrap_err().to_string();
+    assert!(error.contains("requires resource"), "{error}");
+
+    // resources set, built by factories, or provided by modules are present
+    moonbase.set_resource(Config);
+    moonbase.set_resource_factory(|_| async { Ok(Mailer) });
+    let validation = moonbase
+        .validate()
+        .handler(&list_users)
+        .daemon::<Notifier>()
+        .module::<PoolModule>();
+    assert_eq!(validation.missing(), []);
+    assert!(validation.check().is_ok());
+}
+
+#[test]
+fn test_validate_modules() {
+    let moonbase = Moonbase::new();
+    // neither the module it's loaded after nor the one providing the pool is added
+    let validation = moonbase.validate().module::<ReportModule>();
+    let required_by = Requirer::Module(std::any::type_name::<ReportModule>());
+    assert_eq!(
+        validation.missing(),
+        [
+            MissingRequirement {
+                required_by,
+                dependency: Dependency::module::<PoolModule>(),
+            },
+            MissingRequirement {
+                required_by,
+                dependency: Dependency::resource::<Pool>(),
+            },
+        ]
+    );
+    let error = validation.check().unwrap_err().to_string();
+    assert!(error.contains("requires module"), "{error}");
+
+    // the pool module provides the pool, but requires the config
+    let validation = moonbase
+        .validate()
+        .module::<ReportModule>()
+        .module::<PoolModule>();
+    assert_eq!(
+        validation.missing(),
+        [MissingRequirement {
+            required_by: Requirer::Module(std::any::type_name::<PoolModule>()),
+            dependency: Dependency::resource::<Config>(),
+        }]
+    );
+}
+
+#[test]
+fn test_validate_hand_written_daemon() {
+    let moonbase = Moonbase::new();
+    let validation = moonbase.validate().daemon::<Auditor>();
+    assert_eq!(
+        validation.missing(),
+        [MissingRequirement {
+            required_by: Requirer::Daemon(std::any::type_name::<Auditor>()),
+            dependency: Dependency::resource::<Pool>(),
+        }]
+    );
+    // provided by a module added to the validation
+    moonbase.set_resource(Config);
+    let validation = moonbase
+        .validate()
+        .daemon::<Auditor>()
+        .module::<PoolModule>();
+    assert!(validation.check().is_ok());
+}